@@ -3,9 +3,10 @@ mod structs;
 use std::{fs::File, io::Write, path::Path, sync::LazyLock};
 
 use anyhow::{Context, Result};
+use feather_http::send;
 use log::debug;
 use reqwest::{Client, Url};
-use structs::InstallerVersion;
+pub use structs::{GameLoaderVersion, InstallerVersion, LoaderVersion};
 use versions::Versioning;
 
 pub static BASE_FABRIC_URL: LazyLock<Url> =
@@ -24,12 +25,16 @@ impl Default for FabricClient {
 }
 
 impl FabricClient {
+    pub fn with_client(client: Client) -> Self {
+        Self { client }
+    }
+
     pub async fn get_installer_versions(&self) -> Result<Vec<InstallerVersion>> {
         let url = BASE_FABRIC_URL.join("versions/installer")?;
 
         debug!("Getting installer versions from {}", url);
 
-        let response = self.client.get(url).send().await?;
+        let response = send(&self.client, |client| client.get(url.clone())).await?;
 
         let body = response
             .json::<Vec<InstallerVersion>>()
@@ -39,6 +44,25 @@ impl FabricClient {
         Ok(body)
     }
 
+    /// Lists loader versions compatible with the given Minecraft version, newest first.
+    pub async fn get_loader_versions(
+        &self,
+        minecraft_version: &Versioning,
+    ) -> Result<Vec<LoaderVersion>> {
+        let url = BASE_FABRIC_URL.join(&format!("versions/loader/{minecraft_version}"))?;
+
+        debug!("Getting loader versions from {}", url);
+
+        let response = send(&self.client, |client| client.get(url.clone())).await?;
+
+        let body = response
+            .json::<Vec<GameLoaderVersion>>()
+            .await
+            .with_context(|| "Failed to parse loader versions")?;
+
+        Ok(body.into_iter().map(|v| v.loader).collect())
+    }
+
     pub async fn download_installer_jar(
         &self,
         installer_version: &InstallerVersion,
@@ -55,7 +79,7 @@ impl FabricClient {
             "versions/loader/{}/{}/{}/server/jar",
             minecraft_version, fabric_version, installer_version.version
         ))?;
-        let response = self.client.get(url).send().await?;
+        let response = send(&self.client, |client| client.get(url.clone())).await?;
         let body = response.bytes().await?;
 
         let mut file = File::create(directory.join("server.jar"))?;
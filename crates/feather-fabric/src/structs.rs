@@ -8,3 +8,15 @@ pub struct InstallerVersion {
     pub url: String,
     pub stable: bool,
 }
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct LoaderVersion {
+    #[serde(deserialize_with = "Versioning::deserialize_pretty")]
+    pub version: Versioning,
+    pub stable: bool,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct GameLoaderVersion {
+    pub loader: LoaderVersion,
+}
@@ -0,0 +1,226 @@
+mod structs;
+
+use std::{
+    fs::File,
+    io::Write,
+    path::{Path, PathBuf},
+    sync::LazyLock,
+};
+
+use anyhow::{Context, Result, anyhow};
+use feather_http::send;
+use log::debug;
+use reqwest::{Client, Url};
+use structs::Promotions;
+pub use structs::{LoaderVersion, ServerLayout};
+use tokio::process::Command;
+use versions::Versioning;
+
+pub static BASE_FORGE_URL: LazyLock<Url> =
+    LazyLock::new(|| Url::parse("https://maven.minecraftforge.net/").unwrap());
+
+pub static FORGE_PROMOTIONS_URL: LazyLock<Url> = LazyLock::new(|| {
+    Url::parse("https://files.minecraftforge.net/net/minecraftforge/forge/promotions_slim.json")
+        .unwrap()
+});
+
+pub struct ForgeClient {
+    client: Client,
+}
+
+impl Default for ForgeClient {
+    fn default() -> Self {
+        Self {
+            client: Client::new(),
+        }
+    }
+}
+
+impl ForgeClient {
+    /// Uses `client` for both the maven and the promotions file.
+    pub fn with_client(client: Client) -> Self {
+        Self { client }
+    }
+
+    /// Lists every full Forge version (`<minecraft>-<forge>[-<branch>]`) published on the maven.
+    pub async fn get_maven_versions(&self) -> Result<Vec<String>> {
+        let url = BASE_FORGE_URL.join("net/minecraftforge/forge/maven-metadata.xml")?;
+
+        debug!("Getting maven versions from {}", url);
+
+        let response = send(&self.client, |client| client.get(url.clone())).await?;
+        let body = response.text().await?;
+
+        Ok(body
+            .split("<version>")
+            .skip(1)
+            .filter_map(|chunk| chunk.split_once("</version>"))
+            .map(|(version, _)| version.trim().to_string())
+            .collect())
+    }
+
+    /// Lists Forge versions built for the given Minecraft version, newest first.
+    ///
+    /// The promoted "recommended" build is the stable one. Minecraft versions without a
+    /// recommended build fall back to the "latest" promotion.
+    pub async fn get_loader_versions(
+        &self,
+        minecraft_version: &Versioning,
+    ) -> Result<Vec<LoaderVersion>> {
+        debug!("Getting promotions from {}", *FORGE_PROMOTIONS_URL);
+
+        let promotions = send(&self.client, |client| {
+            client.get(FORGE_PROMOTIONS_URL.clone())
+        })
+        .await?
+        .json::<Promotions>()
+        .await
+        .with_context(|| "Failed to parse Forge promotions")?;
+
+        let stable = promotions
+            .promos
+            .get(&format!("{minecraft_version}-recommended"))
+            .or_else(|| {
+                promotions
+                    .promos
+                    .get(&format!("{minecraft_version}-latest"))
+            });
+
+        let prefix = format!("{minecraft_version}-");
+
+        Ok(self
+            .get_maven_versions()
+            .await?
+            .iter()
+            .rev()
+            .filter_map(|full| full.strip_prefix(&prefix))
+            .filter_map(|rest| {
+                let forge = rest.split('-').next()?;
+                Some(LoaderVersion {
+                    version: Versioning::new(forge)?,
+                    stable: stable.is_some_and(|s| s == forge),
+                })
+            })
+            .collect())
+    }
+
+    /// Resolves the full maven version, legacy builds carry a trailing branch such as
+    /// `1.7.10-10.13.4.1614-1.7.10`.
+    pub async fn get_full_version(
+        &self,
+        minecraft_version: &Versioning,
+        forge_version: &Versioning,
+    ) -> Result<String> {
+        let exact = format!("{minecraft_version}-{forge_version}");
+        let branched = format!("{exact}-");
+
+        self.get_maven_versions()
+            .await?
+            .into_iter()
+            .find(|v| *v == exact || v.starts_with(&branched))
+            .ok_or_else(|| anyhow!("Forge {} is not published on the maven", exact))
+    }
+
+    pub async fn download_installer_jar(
+        &self,
+        full_version: &str,
+        directory: &Path,
+    ) -> Result<PathBuf> {
+        let url = BASE_FORGE_URL.join(&format!(
+            "net/minecraftforge/forge/{full_version}/forge-{full_version}-installer.jar"
+        ))?;
+
+        debug!("Downloading installer jar from {}", url);
+
+        let response = send(&self.client, |client| client.get(url.clone())).await?;
+        let body = response.bytes().await?;
+
+        let installer_path = directory.join("forge-installer.jar");
+        let mut file = File::create(&installer_path)?;
+
+        debug!("Writing installer jar to {}", installer_path.display());
+
+        file.write_all(&body)?;
+
+        Ok(installer_path)
+    }
+
+    /// Runs the Forge installer with `--installServer` and detects which launch layout it
+    /// produced.
+    pub async fn install_server(
+        &self,
+        java_executable: &Path,
+        minecraft_version: &Versioning,
+        forge_version: &Versioning,
+        directory: &Path,
+    ) -> Result<ServerLayout> {
+        let full_version = self
+            .get_full_version(minecraft_version, forge_version)
+            .await?;
+        let installer_path = self
+            .download_installer_jar(&full_version, directory)
+            .await?;
+
+        debug!("Running Forge installer {}", full_version);
+
+        // Legacy installers do not accept a target directory and install into the working one.
+        let output = Command::new(java_executable)
+            .arg("-jar")
+            .arg(&installer_path)
+            .arg("--installServer")
+            .current_dir(directory)
+            .output()
+            .await
+            .with_context(|| format!("Failed to run {}", installer_path.display()))?;
+
+        std::fs::remove_file(&installer_path)?;
+
+        // The installer log is left behind on failure to help with debugging.
+        let installer_log = directory.join("forge-installer.jar.log");
+        if !output.status.success() {
+            return Err(anyhow!(
+                "Forge installer exited with {}, see {}",
+                output.status,
+                installer_log.display()
+            ));
+        }
+
+        if installer_log.exists() {
+            std::fs::remove_file(&installer_log)?;
+        }
+
+        Self::detect_layout(&full_version, directory)
+    }
+
+    fn detect_layout(full_version: &str, directory: &Path) -> Result<ServerLayout> {
+        let args_file = PathBuf::from(format!(
+            "libraries/net/minecraftforge/forge/{full_version}/unix_args.txt"
+        ));
+        if directory.join(&args_file).exists() {
+            debug!("Detected argument file layout {}", args_file.display());
+            return Ok(ServerLayout::ArgsFile(args_file));
+        }
+
+        let mut jars = std::fs::read_dir(directory)?
+            .filter_map(|entry| entry.ok())
+            .map(|entry| entry.file_name().to_string_lossy().into_owned())
+            .filter(|name| {
+                name.starts_with("forge-") && name.ends_with(".jar") && !name.contains("installer")
+            })
+            .collect::<Vec<_>>();
+
+        // Prefer the jar matching this exact build if older ones are lying around.
+        jars.sort_by_key(|name| !name.contains(full_version));
+
+        match jars.into_iter().next() {
+            Some(jar) => {
+                debug!("Detected universal jar layout {}", jar);
+                Ok(ServerLayout::Jar(PathBuf::from(jar)))
+            }
+            None => Err(anyhow!(
+                "Forge installer produced neither unix_args.txt nor a forge jar in {}",
+                directory.display()
+            )),
+        }
+    }
+}
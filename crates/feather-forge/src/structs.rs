@@ -0,0 +1,24 @@
+use std::{collections::HashMap, path::PathBuf};
+
+use serde::{Deserialize, Serialize};
+use versions::Versioning;
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct Promotions {
+    pub promos: HashMap<String, String>,
+}
+
+#[derive(Debug)]
+pub struct LoaderVersion {
+    pub version: Versioning,
+    pub stable: bool,
+}
+
+/// The launch layout left behind by the Forge installer, relative to the install directory.
+#[derive(Debug)]
+pub enum ServerLayout {
+    /// Forge 1.17 - 1.20.2 generate `@libraries/.../unix_args.txt` argument files.
+    ArgsFile(PathBuf),
+    /// Older Forge produces a universal `forge-*.jar`, newer Forge a `forge-*-shim.jar`.
+    Jar(PathBuf),
+}
@@ -0,0 +1,60 @@
+use std::time::Duration;
+
+use anyhow::Result;
+use log::warn;
+use reqwest::{Client, RequestBuilder, Response, StatusCode};
+
+const MAX_ATTEMPTS: u32 = 4;
+const INITIAL_BACKOFF: Duration = Duration::from_millis(500);
+
+/// Sends the request built by `request` on `client`, retrying with exponential backoff on
+/// connection errors, timeouts, 429 and 5xx responses. Other error statuses fail immediately.
+pub async fn send<F>(client: &Client, request: F) -> Result<Response>
+where
+    F: Fn(&Client) -> RequestBuilder,
+{
+    retry(|| async { Ok(request(client).send().await?.error_for_status()?) }).await
+}
+
+/// Runs `operation` until it succeeds, fails with a permanent error or runs out of attempts.
+pub async fn retry<F, Fut, T>(mut operation: F) -> Result<T>
+where
+    F: FnMut() -> Fut,
+    Fut: Future<Output = Result<T>>,
+{
+    let mut backoff = INITIAL_BACKOFF;
+    let mut attempt = 1;
+
+    loop {
+        match operation().await {
+            Err(error) if attempt < MAX_ATTEMPTS && is_transient(&error) => {
+                warn!(
+                    "{:#}, retrying in {}ms (attempt {}/{})",
+                    error,
+                    backoff.as_millis(),
+                    attempt + 1,
+                    MAX_ATTEMPTS
+                );
+
+                tokio::time::sleep(backoff).await;
+                backoff *= 2;
+                attempt += 1;
+            }
+            result => return result,
+        }
+    }
+}
+
+/// Whether `error` is worth retrying, such as a dropped connection or a 5xx response.
+pub fn is_transient(error: &anyhow::Error) -> bool {
+    error.chain().any(|cause| {
+        cause.downcast_ref::<reqwest::Error>().is_some_and(|error| {
+            error.is_timeout()
+                || error.is_connect()
+                || error.is_body()
+                || error.status().is_some_and(|status| {
+                    status.is_server_error() || status == StatusCode::TOO_MANY_REQUESTS
+                })
+        })
+    })
+}
@@ -0,0 +1,155 @@
+mod structs;
+
+use std::{
+    fs::File,
+    io::Write,
+    path::{Path, PathBuf},
+    sync::LazyLock,
+};
+
+use anyhow::{Context, Result, anyhow};
+use feather_http::send;
+use log::debug;
+use reqwest::{Client, Url};
+pub use structs::LoaderVersion;
+use structs::MavenVersions;
+use tokio::process::Command;
+use versions::Versioning;
+
+pub static BASE_NEOFORGE_URL: LazyLock<Url> =
+    LazyLock::new(|| Url::parse("https://maven.neoforged.net/").unwrap());
+
+pub struct NeoForgeClient {
+    client: Client,
+}
+
+impl Default for NeoForgeClient {
+    fn default() -> Self {
+        Self {
+            client: Client::new(),
+        }
+    }
+}
+
+impl NeoForgeClient {
+    /// Uses `client` for the maven API and installer downloads.
+    pub fn with_client(client: Client) -> Self {
+        Self { client }
+    }
+
+    /// Lists NeoForge versions built for the given Minecraft version, newest first.
+    ///
+    /// NeoForge versions drop the leading `1.` of the Minecraft version, so 1.21.1 maps onto
+    /// `21.1.*` and 1.21 onto `21.0.*`.
+    pub async fn get_loader_versions(
+        &self,
+        minecraft_version: &Versioning,
+    ) -> Result<Vec<LoaderVersion>> {
+        let url = BASE_NEOFORGE_URL.join("api/maven/versions/releases/net/neoforged/neoforge")?;
+
+        debug!("Getting loader versions from {}", url);
+
+        let response = send(&self.client, |client| client.get(url.clone())).await?;
+
+        let body = response
+            .json::<MavenVersions>()
+            .await
+            .with_context(|| "Failed to parse loader versions")?;
+
+        let prefix = format!(
+            "{}.{}.",
+            minecraft_version.nth(1).unwrap_or_default(),
+            minecraft_version.nth(2).unwrap_or_default()
+        );
+
+        Ok(body
+            .versions
+            .iter()
+            .rev()
+            .filter(|v| v.starts_with(&prefix))
+            .filter_map(|v| {
+                Some(LoaderVersion {
+                    version: Versioning::new(v)?,
+                    stable: !v.contains('-'),
+                })
+            })
+            .collect())
+    }
+
+    pub async fn download_installer_jar(
+        &self,
+        neoforge_version: &Versioning,
+        directory: &Path,
+    ) -> Result<PathBuf> {
+        let url = BASE_NEOFORGE_URL.join(&format!(
+            "releases/net/neoforged/neoforge/{neoforge_version}/neoforge-{neoforge_version}-installer.jar"
+        ))?;
+
+        debug!("Downloading installer jar from {}", url);
+
+        let response = send(&self.client, |client| client.get(url.clone())).await?;
+        let body = response.bytes().await?;
+
+        let installer_path = directory.join("neoforge-installer.jar");
+        let mut file = File::create(&installer_path)?;
+
+        debug!("Writing installer jar to {}", installer_path.display());
+
+        file.write_all(&body)?;
+
+        Ok(installer_path)
+    }
+
+    /// Runs the NeoForge installer with `--installServer` and returns the path of the
+    /// `unix_args.txt` argument file, relative to `directory`.
+    pub async fn install_server(
+        &self,
+        java_executable: &Path,
+        neoforge_version: &Versioning,
+        directory: &Path,
+    ) -> Result<PathBuf> {
+        let installer_path = self
+            .download_installer_jar(neoforge_version, directory)
+            .await?;
+
+        debug!("Running NeoForge installer {}", neoforge_version);
+
+        let output = Command::new(java_executable)
+            .arg("-jar")
+            .arg(&installer_path)
+            .arg("--installServer")
+            .arg(directory)
+            .current_dir(directory)
+            .output()
+            .await
+            .with_context(|| format!("Failed to run {}", installer_path.display()))?;
+
+        std::fs::remove_file(&installer_path)?;
+
+        // The installer log is left behind on failure to help with debugging.
+        let installer_log = directory.join("neoforge-installer.jar.log");
+        if !output.status.success() {
+            return Err(anyhow!(
+                "NeoForge installer exited with {}, see {}",
+                output.status,
+                installer_log.display()
+            ));
+        }
+
+        if installer_log.exists() {
+            std::fs::remove_file(&installer_log)?;
+        }
+
+        let args_file = PathBuf::from(format!(
+            "libraries/net/neoforged/neoforge/{neoforge_version}/unix_args.txt"
+        ));
+        if !directory.join(&args_file).exists() {
+            return Err(anyhow!(
+                "NeoForge installer did not produce {}",
+                args_file.display()
+            ));
+        }
+
+        Ok(args_file)
+    }
+}
@@ -0,0 +1,14 @@
+use serde::{Deserialize, Serialize};
+use versions::Versioning;
+
+#[derive(Debug, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct MavenVersions {
+    pub versions: Vec<String>,
+}
+
+#[derive(Debug)]
+pub struct LoaderVersion {
+    pub version: Versioning,
+    pub stable: bool,
+}
@@ -0,0 +1,127 @@
+mod structs;
+
+use std::{fmt, sync::LazyLock};
+
+use anyhow::{Context, Result, anyhow};
+use feather_http::send;
+use log::debug;
+use reqwest::{Client, Url};
+use structs::ProjectVersions;
+pub use structs::{Build, Channel, Checksums, Download};
+use versions::Versioning;
+
+pub static BASE_PAPER_URL: LazyLock<Url> =
+    LazyLock::new(|| Url::parse("https://fill.papermc.io/v3/").unwrap());
+
+/// Projects published through the PaperMC download API.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Project {
+    Paper,
+    Folia,
+    Velocity,
+    Waterfall,
+}
+
+impl fmt::Display for Project {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{}",
+            match self {
+                Project::Paper => "paper",
+                Project::Folia => "folia",
+                Project::Velocity => "velocity",
+                Project::Waterfall => "waterfall",
+            }
+        )
+    }
+}
+
+pub struct PaperClient {
+    client: Client,
+}
+
+impl Default for PaperClient {
+    fn default() -> Self {
+        // The PaperMC API rejects requests without an identifying user agent.
+        Self {
+            client: Client::builder()
+                .user_agent(concat!("feather/", env!("CARGO_PKG_VERSION")))
+                .build()
+                .unwrap(),
+        }
+    }
+}
+
+impl PaperClient {
+    /// Reuses an existing client, which must send an identifying user agent.
+    pub fn with_client(client: Client) -> Self {
+        Self { client }
+    }
+
+    /// Returns the newest version of a project, proxies are versioned independently of
+    /// Minecraft.
+    pub async fn get_latest_version(&self, project: Project) -> Result<Versioning> {
+        let url = BASE_PAPER_URL.join(&format!("projects/{project}"))?;
+
+        debug!("Getting versions from {}", url);
+
+        let response = send(&self.client, |client| client.get(url.clone())).await?;
+
+        let body = response
+            .json::<ProjectVersions>()
+            .await
+            .with_context(|| format!("Failed to parse {project} versions"))?;
+
+        body.versions
+            .into_values()
+            .flatten()
+            .filter_map(Versioning::new)
+            .max()
+            .ok_or_else(|| anyhow!("{} has no published versions", project))
+    }
+
+    /// Lists builds of a project for the given Minecraft version, newest first.
+    pub async fn get_builds(
+        &self,
+        project: Project,
+        minecraft_version: &Versioning,
+    ) -> Result<Vec<Build>> {
+        let url = BASE_PAPER_URL.join(&format!(
+            "projects/{project}/versions/{minecraft_version}/builds"
+        ))?;
+
+        debug!("Getting builds from {}", url);
+
+        let response = send(&self.client, |client| client.get(url.clone())).await?;
+
+        let mut body = response
+            .json::<Vec<Build>>()
+            .await
+            .with_context(|| format!("Failed to parse {project} builds"))?;
+
+        body.sort_by_key(|build| std::cmp::Reverse(build.id));
+
+        Ok(body)
+    }
+
+    pub async fn get_build(
+        &self,
+        project: Project,
+        minecraft_version: &Versioning,
+        build: u32,
+    ) -> Result<Build> {
+        let url = BASE_PAPER_URL.join(&format!(
+            "projects/{project}/versions/{minecraft_version}/builds/{build}"
+        ))?;
+
+        debug!("Getting build from {}", url);
+
+        let response = send(&self.client, |client| client.get(url.clone())).await?;
+
+        response
+            .json::<Build>()
+            .await
+            .with_context(|| format!("Failed to parse {project} build {build}"))
+    }
+}
@@ -0,0 +1,48 @@
+use std::collections::HashMap;
+
+use serde::{Deserialize, Serialize};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "SCREAMING_SNAKE_CASE")]
+pub enum Channel {
+    Alpha,
+    Beta,
+    Stable,
+    Recommended,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct Checksums {
+    pub sha256: String,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct Download {
+    pub name: String,
+    pub checksums: Checksums,
+    pub size: u64,
+    pub url: String,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct Build {
+    pub id: u32,
+    pub channel: Channel,
+    pub downloads: HashMap<String, Download>,
+}
+
+impl Build {
+    pub fn is_stable(&self) -> bool {
+        matches!(self.channel, Channel::Stable | Channel::Recommended)
+    }
+
+    pub fn server_download(&self) -> Option<&Download> {
+        self.downloads.get("server:default")
+    }
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct ProjectVersions {
+    /// Versions grouped by their major line, e.g. `"3.0.0": ["3.4.0-SNAPSHOT", ...]`.
+    pub versions: HashMap<String, Vec<String>>,
+}
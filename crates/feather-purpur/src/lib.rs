@@ -0,0 +1,68 @@
+mod structs;
+
+use std::sync::LazyLock;
+
+use anyhow::{Context, Result};
+use feather_http::send;
+use log::debug;
+use reqwest::{Client, Url};
+use structs::VersionBuilds;
+pub use structs::{Build, BuildResult, Builds};
+use versions::Versioning;
+
+pub static BASE_PURPUR_URL: LazyLock<Url> =
+    LazyLock::new(|| Url::parse("https://api.purpurmc.org/v2/purpur/").unwrap());
+
+pub struct PurpurClient {
+    client: Client,
+}
+
+impl Default for PurpurClient {
+    fn default() -> Self {
+        Self {
+            client: Client::new(),
+        }
+    }
+}
+
+impl PurpurClient {
+    pub fn with_client(client: Client) -> Self {
+        Self { client }
+    }
+
+    pub async fn get_builds(&self, minecraft_version: &Versioning) -> Result<Builds> {
+        let url = BASE_PURPUR_URL.join(&minecraft_version.to_string())?;
+
+        debug!("Getting builds from {}", url);
+
+        let response = send(&self.client, |client| client.get(url.clone())).await?;
+
+        let body = response
+            .json::<VersionBuilds>()
+            .await
+            .with_context(|| "Failed to parse Purpur builds")?;
+
+        Ok(body.builds)
+    }
+
+    pub async fn get_build(&self, minecraft_version: &Versioning, build: &str) -> Result<Build> {
+        let url = BASE_PURPUR_URL.join(&format!("{minecraft_version}/{build}"))?;
+
+        debug!("Getting build from {}", url);
+
+        let response = send(&self.client, |client| client.get(url.clone())).await?;
+
+        response
+            .json::<Build>()
+            .await
+            .with_context(|| format!("Failed to parse Purpur build {build}"))
+    }
+
+    /// URL of a build's server jar.
+    ///
+    /// Purpur publishes no sha256 for its builds, so the jar can only be checked against the
+    /// md5 in [`Build::md5`].
+    pub fn download_url(&self, minecraft_version: &Versioning, build: &str) -> Result<Url> {
+        Ok(BASE_PURPUR_URL.join(&format!("{minecraft_version}/{build}/download"))?)
+    }
+}
@@ -0,0 +1,27 @@
+use serde::{Deserialize, Serialize};
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct Builds {
+    pub latest: String,
+    pub all: Vec<String>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct VersionBuilds {
+    pub builds: Builds,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "SCREAMING_SNAKE_CASE")]
+pub enum BuildResult {
+    Success,
+    Failure,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct Build {
+    pub build: String,
+    /// The only digest the Purpur API publishes for a build's jar.
+    pub md5: String,
+    pub result: BuildResult,
+}
@@ -0,0 +1,158 @@
+mod structs;
+
+use std::{
+    fs::File,
+    io::Write,
+    path::{Path, PathBuf},
+    sync::LazyLock,
+};
+
+use anyhow::{Context, Result, anyhow};
+use feather_http::send;
+use log::debug;
+use reqwest::{Client, Url};
+pub use structs::{GameLoaderVersion, InstallerVersion, LoaderVersion};
+use tokio::process::Command;
+use versions::Versioning;
+
+pub static BASE_QUILT_URL: LazyLock<Url> =
+    LazyLock::new(|| Url::parse("https://meta.quiltmc.org/v3/").unwrap());
+
+/// Jar produced by the Quilt installer that boots the loader on top of `server.jar`.
+pub const SERVER_LAUNCH_JAR: &str = "quilt-server-launch.jar";
+
+pub struct QuiltClient {
+    client: Client,
+}
+
+impl Default for QuiltClient {
+    fn default() -> Self {
+        Self {
+            client: Client::new(),
+        }
+    }
+}
+
+impl QuiltClient {
+    /// Uses `client` for the meta API and installer downloads.
+    pub fn with_client(client: Client) -> Self {
+        Self { client }
+    }
+
+    pub async fn get_installer_versions(&self) -> Result<Vec<InstallerVersion>> {
+        let url = BASE_QUILT_URL.join("versions/installer")?;
+
+        debug!("Getting installer versions from {}", url);
+
+        let response = send(&self.client, |client| client.get(url.clone())).await?;
+
+        let body = response
+            .json::<Vec<InstallerVersion>>()
+            .await
+            .with_context(|| "Failed to parse installer versions")?;
+
+        Ok(body)
+    }
+
+    /// Lists loader versions compatible with the given Minecraft version, newest first.
+    pub async fn get_loader_versions(
+        &self,
+        minecraft_version: &Versioning,
+    ) -> Result<Vec<LoaderVersion>> {
+        let url = BASE_QUILT_URL.join(&format!("versions/loader/{minecraft_version}"))?;
+
+        debug!("Getting loader versions from {}", url);
+
+        let response = send(&self.client, |client| client.get(url.clone())).await?;
+
+        let body = response
+            .json::<Vec<GameLoaderVersion>>()
+            .await
+            .with_context(|| "Failed to parse loader versions")?;
+
+        Ok(body.into_iter().map(|v| v.loader).collect())
+    }
+
+    pub async fn download_installer_jar(
+        &self,
+        installer_version: &InstallerVersion,
+        directory: &Path,
+    ) -> Result<PathBuf> {
+        debug!(
+            "Downloading installer jar {} from {}",
+            installer_version.version, installer_version.url
+        );
+
+        let response = send(&self.client, |client| client.get(&installer_version.url)).await?;
+        let body = response.bytes().await?;
+
+        let installer_path = directory.join("quilt-installer.jar");
+        let mut file = File::create(&installer_path)?;
+
+        debug!("Writing installer jar to {}", installer_path.display());
+
+        file.write_all(&body)?;
+
+        Ok(installer_path)
+    }
+
+    /// Runs the Quilt installer headlessly and returns the path of the launch jar.
+    ///
+    /// The installer also downloads the vanilla `server.jar` the launch jar expects next to it.
+    pub async fn install_server(
+        &self,
+        java_executable: &Path,
+        minecraft_version: &Versioning,
+        loader_version: &Versioning,
+        directory: &Path,
+    ) -> Result<PathBuf> {
+        let installer_version = self
+            .get_installer_versions()
+            .await?
+            .into_iter()
+            .next()
+            .ok_or_else(|| anyhow!("Quilt meta returned no installer versions"))?;
+
+        let installer_path = self
+            .download_installer_jar(&installer_version, directory)
+            .await?;
+
+        debug!(
+            "Running Quilt installer {} for {} {}",
+            installer_version.version, minecraft_version, loader_version
+        );
+
+        let output = Command::new(java_executable)
+            .arg("-jar")
+            .arg(&installer_path)
+            .args(["install", "server"])
+            .arg(minecraft_version.to_string())
+            .arg(loader_version.to_string())
+            .arg("--download-server")
+            .arg(format!("--install-dir={}", directory.display()))
+            .current_dir(directory)
+            .output()
+            .await
+            .with_context(|| format!("Failed to run {}", installer_path.display()))?;
+
+        std::fs::remove_file(&installer_path)?;
+
+        if !output.status.success() {
+            return Err(anyhow!(
+                "Quilt installer exited with {}: {}",
+                output.status,
+                String::from_utf8_lossy(&output.stderr).trim()
+            ));
+        }
+
+        let launch_jar = directory.join(SERVER_LAUNCH_JAR);
+        if !launch_jar.exists() {
+            return Err(anyhow!(
+                "Quilt installer did not produce {}",
+                launch_jar.display()
+            ));
+        }
+
+        Ok(launch_jar)
+    }
+}
@@ -0,0 +1,29 @@
+use serde::{Deserialize, Serialize};
+use versions::Versioning;
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct InstallerVersion {
+    #[serde(deserialize_with = "Versioning::deserialize_pretty")]
+    pub version: Versioning,
+    pub url: String,
+    pub maven: String,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct LoaderVersion {
+    #[serde(deserialize_with = "Versioning::deserialize_pretty")]
+    pub version: Versioning,
+    pub maven: String,
+}
+
+impl LoaderVersion {
+    /// Quilt meta has no stability flag, pre-releases carry a `-beta.N` style suffix instead.
+    pub fn is_stable(&self) -> bool {
+        !self.version.to_string().contains('-')
+    }
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct GameLoaderVersion {
+    pub loader: LoaderVersion,
+}
@@ -0,0 +1,293 @@
+use anyhow::{Context, Result, anyhow};
+use flate2::{Compression, read::GzDecoder, write::GzEncoder};
+use serde::{Deserialize, Serialize};
+use std::{
+    collections::BTreeSet,
+    fs::File,
+    io::{BufReader, BufWriter, Read},
+    path::{Path, PathBuf},
+};
+use tar::{Archive, Builder, Header};
+
+use crate::{
+    checksum::Checksum, java::JavaVersion, minecraft::ServerLaunch, modpack::MinecraftProfile,
+};
+
+/// Name of the lockfile at the root of a bundle.
+pub const LOCKFILE: &str = "feather.lock";
+/// Directory holding the unpacked JDK inside a bundle.
+pub const JAVA_DIR: &str = "java";
+/// Directory holding the installed server inside a bundle.
+pub const SERVER_DIR: &str = "server";
+
+/// Everything `feather init --from-bundle` needs to reproduce an install without network access.
+#[derive(Serialize, Deserialize, Debug)]
+pub struct BundleLock {
+    pub profile: MinecraftProfile,
+    pub java_version: JavaVersion,
+    /// Unpacked JDK, relative to [`JAVA_DIR`].
+    pub jdk_dir: PathBuf,
+    pub launch: ServerLaunch,
+    pub files: Vec<BundleFile>,
+}
+
+#[derive(Serialize, Deserialize, Debug)]
+pub struct BundleFile {
+    /// Path relative to the bundle root.
+    pub path: PathBuf,
+    pub sha256: String,
+}
+
+/// Records a sha256 for every regular file under `dir`, stored under `prefix` in the bundle.
+pub fn hash_files(dir: &Path, prefix: &Path) -> Result<Vec<BundleFile>> {
+    let mut files = Vec::new();
+    let mut pending = vec![dir.to_path_buf()];
+
+    while let Some(current) = pending.pop() {
+        for entry in std::fs::read_dir(&current)
+            .with_context(|| format!("Failed to read directory: {}", current.display()))?
+        {
+            let entry = entry?;
+            let file_type = entry.file_type()?;
+
+            if file_type.is_dir() {
+                pending.push(entry.path());
+            } else if file_type.is_file() {
+                files.push(BundleFile {
+                    path: prefix.join(entry.path().strip_prefix(dir)?),
+                    sha256: sha256_file(&entry.path())?,
+                });
+            }
+        }
+    }
+
+    files.sort_by(|a, b| a.path.cmp(&b.path));
+    Ok(files)
+}
+
+/// Packs the lockfile, the directory holding the unpacked JDK and the server directory into a
+/// gzipped tarball.
+pub fn write(lock: &BundleLock, jdk_root: &Path, server_dir: &Path, output: &Path) -> Result<()> {
+    let lockfile = serde_json::to_vec_pretty(lock).context("Failed to serialize lockfile")?;
+    let jdk_root_name = lock
+        .jdk_dir
+        .components()
+        .next()
+        .context("Lockfile has an empty JDK path")?;
+
+    let file = File::create(output)
+        .with_context(|| format!("Failed to create bundle: {}", output.display()))?;
+    let mut builder = Builder::new(GzEncoder::new(BufWriter::new(file), Compression::default()));
+    // JDKs ship relative symlinks which must stay links inside the bundle.
+    builder.follow_symlinks(false);
+
+    let mut header = Header::new_gnu();
+    header.set_size(lockfile.len() as u64);
+    header.set_mode(0o644);
+    header.set_cksum();
+    builder.append_data(&mut header, LOCKFILE, lockfile.as_slice())?;
+
+    builder
+        .append_dir_all(Path::new(JAVA_DIR).join(jdk_root_name), jdk_root)
+        .context("Failed to add the JDK to the bundle")?;
+    builder
+        .append_dir_all(SERVER_DIR, server_dir)
+        .context("Failed to add the server to the bundle")?;
+
+    builder
+        .into_inner()?
+        .finish()
+        .context("Failed to finish bundle")?;
+
+    Ok(())
+}
+
+/// Unpacks a bundle into `destination` and checks every file against the lockfile.
+pub fn extract(bundle: &Path, destination: &Path) -> Result<BundleLock> {
+    let file = File::open(bundle)
+        .with_context(|| format!("Failed to open bundle: {}", bundle.display()))?;
+    Archive::new(GzDecoder::new(BufReader::new(file)))
+        .unpack(destination)
+        .with_context(|| format!("Failed to unpack bundle: {}", bundle.display()))?;
+
+    let lockfile = std::fs::read(destination.join(LOCKFILE))
+        .with_context(|| format!("Bundle {} has no {}", bundle.display(), LOCKFILE))?;
+    let lock: BundleLock = serde_json::from_slice(&lockfile).context("Failed to parse lockfile")?;
+
+    let mismatched = lock
+        .files
+        .iter()
+        .filter(|file| {
+            !sha256_file(&destination.join(&file.path))
+                .is_ok_and(|sha256| sha256.eq_ignore_ascii_case(&file.sha256))
+        })
+        .map(|file| file.path.display().to_string())
+        .collect::<Vec<_>>();
+
+    if !mismatched.is_empty() {
+        return Err(anyhow!(
+            "Bundle files are missing or do not match the lockfile: {}",
+            mismatched.join(", ")
+        ));
+    }
+
+    let unlisted = unlisted_files(destination, &lock)?;
+    if !unlisted.is_empty() {
+        return Err(anyhow!(
+            "Bundle contains files that are not in the lockfile: {}",
+            unlisted.join(", ")
+        ));
+    }
+
+    Ok(lock)
+}
+
+/// Everything unpacked besides the lockfile itself and the files it lists. Symlinks are only
+/// expected inside the JDK, which ships relative ones.
+fn unlisted_files(destination: &Path, lock: &BundleLock) -> Result<Vec<String>> {
+    let listed = lock
+        .files
+        .iter()
+        .map(|file| file.path.as_path())
+        .collect::<BTreeSet<_>>();
+    let mut unlisted = Vec::new();
+    let mut pending = vec![destination.to_path_buf()];
+
+    while let Some(current) = pending.pop() {
+        for entry in std::fs::read_dir(&current)
+            .with_context(|| format!("Failed to read directory: {}", current.display()))?
+        {
+            let entry = entry?;
+            let file_type = entry.file_type()?;
+            let path = entry.path();
+            let relative = path.strip_prefix(destination)?;
+
+            if file_type.is_dir() {
+                pending.push(path);
+            } else if file_type.is_symlink() && relative.starts_with(JAVA_DIR) {
+                continue;
+            } else if !(file_type.is_file()
+                && (relative == Path::new(LOCKFILE) || listed.contains(relative)))
+            {
+                unlisted.push(relative.display().to_string());
+            }
+        }
+    }
+
+    unlisted.sort();
+    Ok(unlisted)
+}
+
+fn sha256_file(path: &Path) -> Result<String> {
+    let mut file =
+        File::open(path).with_context(|| format!("Failed to open {}", path.display()))?;
+    let mut hasher = Checksum::Sha256(String::new()).hasher();
+    let mut buffer = vec![0; 64 * 1024];
+
+    loop {
+        let read = file.read(&mut buffer)?;
+        if read == 0 {
+            break;
+        }
+        hasher.update(&buffer[..read]);
+    }
+
+    Ok(hasher.finish())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::os::unix::fs::symlink;
+    use tempfile::TempDir;
+    use versions::Versioning;
+
+    /// A JDK with a relative symlink and a server, hashed into a lock.
+    fn staging() -> (TempDir, BundleLock) {
+        let dir = tempfile::tempdir().unwrap();
+        let jdk_root = dir.path().join("jdk-21");
+        let server_dir = dir.path().join("server");
+        std::fs::create_dir_all(jdk_root.join("bin")).unwrap();
+        std::fs::create_dir_all(jdk_root.join("lib")).unwrap();
+        std::fs::create_dir_all(&server_dir).unwrap();
+        std::fs::write(jdk_root.join("bin/java"), "java").unwrap();
+        symlink("../bin/java", jdk_root.join("lib/java")).unwrap();
+        std::fs::write(server_dir.join("server.jar"), "server").unwrap();
+
+        let mut files = hash_files(&jdk_root, &Path::new(JAVA_DIR).join("jdk-21")).unwrap();
+        files.extend(hash_files(&server_dir, Path::new(SERVER_DIR)).unwrap());
+
+        let lock = BundleLock {
+            profile: MinecraftProfile {
+                version: Versioning::new("1.21.1").unwrap(),
+                loader: None,
+                modpack: None,
+            },
+            java_version: JavaVersion(21),
+            jdk_dir: PathBuf::from("jdk-21"),
+            launch: ServerLaunch::Jar(PathBuf::from("server.jar")),
+            files,
+        };
+
+        (dir, lock)
+    }
+
+    fn pack_and_extract(dir: &TempDir, lock: &BundleLock) -> Result<BundleLock> {
+        let bundle = dir.path().join("bundle.tar.gz");
+        write(
+            lock,
+            &dir.path().join("jdk-21"),
+            &dir.path().join("server"),
+            &bundle,
+        )
+        .unwrap();
+
+        extract(&bundle, &dir.path().join("extracted"))
+    }
+
+    #[test]
+    fn extracts_listed_files_and_jdk_symlinks() {
+        let (dir, lock) = staging();
+
+        let extracted = pack_and_extract(&dir, &lock).unwrap();
+
+        assert_eq!(extracted.files.len(), 2);
+        assert_eq!(
+            std::fs::read(dir.path().join("extracted/java/jdk-21/lib/java")).unwrap(),
+            b"java"
+        );
+    }
+
+    #[test]
+    fn rejects_tampered_files() {
+        let (dir, lock) = staging();
+        std::fs::write(dir.path().join("server/server.jar"), "tampered").unwrap();
+
+        let error = pack_and_extract(&dir, &lock).unwrap_err();
+
+        assert!(error.to_string().contains("do not match the lockfile"));
+        assert!(error.to_string().contains("server/server.jar"));
+    }
+
+    #[test]
+    fn rejects_unlisted_files() {
+        let (dir, lock) = staging();
+        std::fs::write(dir.path().join("server/extra.jar"), "extra").unwrap();
+
+        let error = pack_and_extract(&dir, &lock).unwrap_err();
+
+        assert!(error.to_string().contains("not in the lockfile"));
+        assert!(error.to_string().contains("server/extra.jar"));
+    }
+
+    #[test]
+    fn rejects_symlinks_outside_java() {
+        let (dir, lock) = staging();
+        symlink("/etc/passwd", dir.path().join("server/passwd")).unwrap();
+
+        let error = pack_and_extract(&dir, &lock).unwrap_err();
+
+        assert!(error.to_string().contains("not in the lockfile"));
+        assert!(error.to_string().contains("server/passwd"));
+    }
+}
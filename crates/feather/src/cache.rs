@@ -0,0 +1,239 @@
+use anyhow::{Context, Result, anyhow};
+use std::{
+    fs::File,
+    io::Read,
+    os::unix::fs::MetadataExt,
+    path::{Path, PathBuf},
+};
+
+use crate::checksum::Checksum;
+
+/// Content-addressed store of downloaded jars shared by every server.
+///
+/// Entries live at `<dir>/<algorithm>/<first two hex digits>/<hash>` and are hardlinked into
+/// server directories, so an entry whose link count drops to one is no longer referenced.
+#[derive(Debug, Clone)]
+pub struct Cache {
+    dir: PathBuf,
+}
+
+#[derive(Debug)]
+pub struct CacheEntry {
+    pub path: PathBuf,
+    pub checksum: Checksum,
+    pub size: u64,
+    /// Number of server files hardlinked to this entry.
+    pub references: u64,
+}
+
+impl Cache {
+    /// Only digests that are safe to use as a content address are cached.
+    const ALGORITHMS: [&str; 3] = ["sha512", "sha256", "sha1"];
+
+    pub fn new(dir: &Path) -> Self {
+        Self {
+            dir: dir.to_path_buf(),
+        }
+    }
+
+    /// Where the file with `checksum` is stored, `None` for digests too weak to key on.
+    ///
+    /// Hashes come from modpack metadata, so anything but hex of the algorithm's exact length
+    /// is refused rather than turned into a path.
+    pub fn entry_path(&self, checksum: &Checksum) -> Option<PathBuf> {
+        let algorithm = checksum.algorithm();
+        let length = match algorithm {
+            "sha512" => 128,
+            "sha256" => 64,
+            "sha1" => 40,
+            _ => return None,
+        };
+
+        let hash = checksum.expected().to_ascii_lowercase();
+        if hash.len() != length
+            || !hash
+                .bytes()
+                .all(|b| b.is_ascii_digit() || (b'a'..=b'f').contains(&b))
+        {
+            return None;
+        }
+
+        Some(self.dir.join(algorithm).join(&hash[..2]).join(hash))
+    }
+
+    /// Places a cached file at `destination`, hardlinking when possible and copying when the
+    /// cache is on another filesystem.
+    pub async fn link(&self, entry: &Path, destination: &Path) -> Result<()> {
+        if tokio::fs::symlink_metadata(destination).await.is_ok() {
+            tokio::fs::remove_file(destination)
+                .await
+                .with_context(|| format!("Failed to replace {}", destination.display()))?;
+        }
+
+        if let Err(error) = tokio::fs::hard_link(entry, destination).await {
+            tracing::debug!(
+                "Hardlinking {} failed ({}), copying instead",
+                entry.display(),
+                error
+            );
+
+            tokio::fs::copy(entry, destination)
+                .await
+                .with_context(|| format!("Failed to copy {}", entry.display()))?;
+        }
+
+        Ok(())
+    }
+
+    pub fn entries(&self) -> Result<Vec<CacheEntry>> {
+        let mut entries = Vec::new();
+
+        for algorithm in Self::ALGORITHMS {
+            let algorithm_dir = self.dir.join(algorithm);
+            if !algorithm_dir.is_dir() {
+                continue;
+            }
+
+            for prefix in read_dir(&algorithm_dir)? {
+                for path in read_dir(&prefix)? {
+                    let Some(hash) = path.file_name().and_then(|name| name.to_str()) else {
+                        continue;
+                    };
+                    // Interrupted downloads leave `.part` files behind.
+                    if hash.ends_with(".part") {
+                        continue;
+                    }
+
+                    let metadata = path
+                        .metadata()
+                        .with_context(|| format!("Failed to stat {}", path.display()))?;
+
+                    entries.push(CacheEntry {
+                        checksum: Checksum::from_packwiz(algorithm, hash)?,
+                        size: metadata.len(),
+                        references: metadata.nlink().saturating_sub(1),
+                        path,
+                    });
+                }
+            }
+        }
+
+        Ok(entries)
+    }
+
+    /// Removes entries that no server links to anymore, returning them.
+    pub fn gc(&self) -> Result<Vec<CacheEntry>> {
+        let mut removed = Vec::new();
+
+        for entry in self.entries()? {
+            if entry.references == 0 {
+                std::fs::remove_file(&entry.path)
+                    .with_context(|| format!("Failed to remove {}", entry.path.display()))?;
+                removed.push(entry);
+            }
+        }
+
+        Ok(removed)
+    }
+
+    /// Rehashes every entry and returns the ones whose contents no longer match their name.
+    pub fn verify(&self) -> Result<Vec<(CacheEntry, anyhow::Error)>> {
+        let mut corrupted = Vec::new();
+
+        for entry in self.entries()? {
+            let mut file = File::open(&entry.path)
+                .with_context(|| format!("Failed to open {}", entry.path.display()))?;
+            let mut hasher = entry.checksum.hasher();
+            let mut buffer = vec![0; 64 * 1024];
+
+            loop {
+                let read = file
+                    .read(&mut buffer)
+                    .with_context(|| format!("Failed to read {}", entry.path.display()))?;
+                if read == 0 {
+                    break;
+                }
+                hasher.update(&buffer[..read]);
+            }
+
+            if let Err(error) = entry.checksum.verify_digest(&hasher.finish()) {
+                corrupted.push((entry, error));
+            }
+        }
+
+        Ok(corrupted)
+    }
+}
+
+fn read_dir(dir: &Path) -> Result<Vec<PathBuf>> {
+    std::fs::read_dir(dir)
+        .with_context(|| format!("Failed to read {}", dir.display()))?
+        .map(|entry| {
+            entry
+                .map(|entry| entry.path())
+                .map_err(|e| anyhow!("Failed to read {}: {}", dir.display(), e))
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn cache() -> Cache {
+        Cache::new(Path::new("/opt/feather/cache"))
+    }
+
+    #[test]
+    fn entry_path_uses_algorithm_and_prefix() {
+        let hash = "ab".repeat(20);
+
+        assert_eq!(
+            cache().entry_path(&Checksum::Sha1(hash.clone())),
+            Some(PathBuf::from(format!("/opt/feather/cache/sha1/ab/{hash}")))
+        );
+    }
+
+    #[test]
+    fn entry_path_lowercases_hashes() {
+        let path = cache()
+            .entry_path(&Checksum::Sha256("AB".repeat(32)))
+            .unwrap();
+
+        assert!(path.ends_with(format!("ab/{}", "ab".repeat(32))));
+    }
+
+    #[test]
+    fn entry_path_rejects_absolute_paths() {
+        assert_eq!(
+            cache().entry_path(&Checksum::Sha1("/etc/shadow".to_string())),
+            None
+        );
+    }
+
+    #[test]
+    fn entry_path_rejects_traversal() {
+        let hash = format!("../../{}", "a".repeat(34));
+
+        assert_eq!(cache().entry_path(&Checksum::Sha1(hash)), None);
+    }
+
+    #[test]
+    fn entry_path_rejects_non_ascii() {
+        let hash = format!("é{}", "a".repeat(38));
+
+        assert_eq!(hash.len(), 40);
+        assert_eq!(cache().entry_path(&Checksum::Sha1(hash)), None);
+    }
+
+    #[test]
+    fn entry_path_rejects_wrong_lengths() {
+        assert_eq!(cache().entry_path(&Checksum::Sha1("a".repeat(39))), None);
+        assert_eq!(cache().entry_path(&Checksum::Sha512("a".repeat(64))), None);
+    }
+
+    #[test]
+    fn entry_path_skips_weak_digests() {
+        assert_eq!(cache().entry_path(&Checksum::Md5("a".repeat(32))), None);
+    }
+}
@@ -0,0 +1,220 @@
+use anyhow::{Result, anyhow};
+use md5::Md5;
+use sha1::{Digest, Sha1};
+use sha2::{Sha256, Sha512};
+use std::fmt;
+
+/// An expected digest published alongside a download, stored as lowercase hex.
+///
+/// CurseForge's murmur2 fingerprint is the exception and is stored as a decimal number.
+#[derive(Debug, Clone)]
+pub enum Checksum {
+    Sha1(String),
+    Sha256(String),
+    Sha512(String),
+    Md5(String),
+    Murmur2(String),
+}
+
+impl Checksum {
+    /// Parses a packwiz `hash-format`/`hash` pair.
+    pub fn from_packwiz(format: &str, hash: &str) -> Result<Self> {
+        let hash = hash.to_ascii_lowercase();
+
+        Ok(match format {
+            "sha1" => Checksum::Sha1(hash),
+            "sha256" => Checksum::Sha256(hash),
+            "sha512" => Checksum::Sha512(hash),
+            "md5" => Checksum::Md5(hash),
+            "murmur2" => {
+                let fingerprint = hash
+                    .parse::<u32>()
+                    .or_else(|_| u32::from_str_radix(&hash, 16))
+                    .map_err(|_| anyhow!("Invalid murmur2 hash: {}", hash))?;
+                Checksum::Murmur2(fingerprint.to_string())
+            }
+            _ => return Err(anyhow!("Unsupported hash format: {}", format)),
+        })
+    }
+
+    pub fn expected(&self) -> &str {
+        match self {
+            Checksum::Sha1(hash)
+            | Checksum::Sha256(hash)
+            | Checksum::Sha512(hash)
+            | Checksum::Md5(hash)
+            | Checksum::Murmur2(hash) => hash,
+        }
+    }
+
+    /// Starts an incremental digest, used when the data is streamed rather than buffered.
+    pub fn hasher(&self) -> ChecksumHasher {
+        match self {
+            Checksum::Sha1(_) => ChecksumHasher::Sha1(Sha1::new()),
+            Checksum::Sha256(_) => ChecksumHasher::Sha256(Sha256::new()),
+            Checksum::Sha512(_) => ChecksumHasher::Sha512(Sha512::new()),
+            Checksum::Md5(_) => ChecksumHasher::Md5(Md5::new()),
+            Checksum::Murmur2(_) => ChecksumHasher::Murmur2(Vec::new()),
+        }
+    }
+
+    pub fn digest(&self, bytes: &[u8]) -> String {
+        let mut hasher = self.hasher();
+        hasher.update(bytes);
+        hasher.finish()
+    }
+
+    pub fn verify(&self, bytes: &[u8]) -> Result<()> {
+        self.verify_digest(&self.digest(bytes))
+    }
+
+    /// Compares an already computed digest, as returned by [`ChecksumHasher::finish`].
+    pub fn verify_digest(&self, actual: &str) -> Result<()> {
+        if !actual.eq_ignore_ascii_case(self.expected()) {
+            return Err(anyhow!(
+                "{} mismatch: expected {}, got {}",
+                self.algorithm(),
+                self.expected(),
+                actual
+            ));
+        }
+
+        Ok(())
+    }
+
+    pub fn algorithm(&self) -> &'static str {
+        match self {
+            Checksum::Sha1(_) => "sha1",
+            Checksum::Sha256(_) => "sha256",
+            Checksum::Sha512(_) => "sha512",
+            Checksum::Md5(_) => "md5",
+            Checksum::Murmur2(_) => "murmur2",
+        }
+    }
+}
+
+pub enum ChecksumHasher {
+    Sha1(Sha1),
+    Sha256(Sha256),
+    Sha512(Sha512),
+    Md5(Md5),
+    /// The fingerprint's seed depends on the total length, so its input is collected first.
+    Murmur2(Vec<u8>),
+}
+
+impl ChecksumHasher {
+    pub fn update(&mut self, bytes: &[u8]) {
+        match self {
+            ChecksumHasher::Sha1(hasher) => hasher.update(bytes),
+            ChecksumHasher::Sha256(hasher) => hasher.update(bytes),
+            ChecksumHasher::Sha512(hasher) => hasher.update(bytes),
+            ChecksumHasher::Md5(hasher) => hasher.update(bytes),
+            ChecksumHasher::Murmur2(buffer) => buffer.extend_from_slice(bytes),
+        }
+    }
+
+    pub fn finish(self) -> String {
+        match self {
+            ChecksumHasher::Sha1(hasher) => format!("{:x}", hasher.finalize()),
+            ChecksumHasher::Sha256(hasher) => format!("{:x}", hasher.finalize()),
+            ChecksumHasher::Sha512(hasher) => format!("{:x}", hasher.finalize()),
+            ChecksumHasher::Md5(hasher) => format!("{:x}", hasher.finalize()),
+            ChecksumHasher::Murmur2(buffer) => curseforge_fingerprint(&buffer).to_string(),
+        }
+    }
+}
+
+impl fmt::Display for Checksum {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}:{}", self.algorithm(), self.expected())
+    }
+}
+
+/// CurseForge's fingerprint: murmur2 with seed 1 over the file with whitespace bytes removed.
+fn curseforge_fingerprint(bytes: &[u8]) -> u32 {
+    let normalized = bytes
+        .iter()
+        .copied()
+        .filter(|b| !matches!(b, 9 | 10 | 13 | 32))
+        .collect::<Vec<_>>();
+
+    murmur2(&normalized, 1)
+}
+
+fn murmur2(data: &[u8], seed: u32) -> u32 {
+    const M: u32 = 0x5bd1_e995;
+    const R: u32 = 24;
+
+    let mut h = seed ^ data.len() as u32;
+
+    let mut chunks = data.chunks_exact(4);
+    for chunk in &mut chunks {
+        let mut k = u32::from_le_bytes([chunk[0], chunk[1], chunk[2], chunk[3]]);
+        k = k.wrapping_mul(M);
+        k ^= k >> R;
+        k = k.wrapping_mul(M);
+
+        h = h.wrapping_mul(M);
+        h ^= k;
+    }
+
+    let tail = chunks.remainder();
+    if tail.len() >= 3 {
+        h ^= u32::from(tail[2]) << 16;
+    }
+    if tail.len() >= 2 {
+        h ^= u32::from(tail[1]) << 8;
+    }
+    if !tail.is_empty() {
+        h ^= u32::from(tail[0]);
+        h = h.wrapping_mul(M);
+    }
+
+    h ^= h >> 13;
+    h = h.wrapping_mul(M);
+    h ^= h >> 15;
+
+    h
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// SMHasher's verification value for MurmurHash2: hash the keys `[0]`, `[0, 1]`, ... with
+    /// seeds 256 down to 1, then hash the concatenated results with seed 0.
+    #[test]
+    fn murmur2_matches_smhasher_verification() {
+        let key = (0..=255).collect::<Vec<u8>>();
+        let hashes = (0..256)
+            .flat_map(|i| murmur2(&key[..i], 256 - i as u32).to_le_bytes())
+            .collect::<Vec<_>>();
+
+        assert_eq!(murmur2(&hashes, 0), 0x2786_4c1e);
+    }
+
+    #[test]
+    fn curseforge_fingerprint_ignores_whitespace() {
+        let fingerprint = |bytes: &[u8]| Checksum::Murmur2(String::new()).digest(bytes);
+
+        assert_eq!(fingerprint(b"helloworld"), "2824650221");
+        assert_eq!(fingerprint(b"hello world\r\n"), "2824650221");
+        assert_eq!(fingerprint(b"\thello\nworld "), "2824650221");
+        assert_eq!(fingerprint(b""), "1540447798");
+        assert_eq!(fingerprint(b" \t\r\n"), "1540447798");
+    }
+
+    #[test]
+    fn parses_packwiz_murmur2_as_decimal_or_hex() {
+        let hash = |hash: &str| {
+            Checksum::from_packwiz("murmur2", hash)
+                .unwrap()
+                .expected()
+                .to_string()
+        };
+
+        assert_eq!(hash("2824650221"), "2824650221");
+        assert_eq!(hash("A85CBDED"), "2824650221");
+        assert!(Checksum::from_packwiz("murmur2", "not a hash").is_err());
+    }
+}
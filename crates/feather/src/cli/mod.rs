@@ -1,6 +1,13 @@
-use std::str::FromStr;
+pub mod printer;
+
+use std::{path::PathBuf, str::FromStr};
 
 use clap::{Args, Parser, Subcommand};
+use reqwest::Url;
+use versions::Versioning;
+
+use crate::{instance, java::JavaVersion, modpack::LoaderType, proxy::ProxyKind};
+use printer::Printer;
 
 #[derive(Parser)]
 #[command(name = "feather")]
@@ -8,6 +15,25 @@ use clap::{Args, Parser, Subcommand};
 pub struct Cli {
     #[command(subcommand)]
     pub command: Commands,
+
+    /// Hide progress bars, pass twice to silence everything but errors
+    #[arg(short, long, global = true, action = clap::ArgAction::Count)]
+    pub quiet: u8,
+
+    /// Log debug output instead of progress bars
+    #[arg(short, long, global = true, conflicts_with = "quiet")]
+    pub verbose: bool,
+}
+
+impl Cli {
+    pub fn printer(&self) -> Printer {
+        match (self.quiet, self.verbose) {
+            (0, true) => Printer::Verbose,
+            (0, false) => Printer::Default,
+            (1, _) => Printer::Quiet,
+            _ => Printer::Silent,
+        }
+    }
 }
 
 #[derive(Clone, Default, Debug)]
@@ -15,6 +41,7 @@ pub enum JavaSelection {
     #[default]
     Auto,
     System,
+    Path(PathBuf),
 }
 
 impl FromStr for JavaSelection {
@@ -24,23 +51,132 @@ impl FromStr for JavaSelection {
         Ok(match s {
             "auto" => JavaSelection::Auto,
             "system" => JavaSelection::System,
-            _ => anyhow::bail!("Invalid Java selection"),
+            "" => anyhow::bail!("Invalid Java selection"),
+            _ => JavaSelection::Path(PathBuf::from(s)),
+        })
+    }
+}
+
+#[derive(Clone, Default, Debug)]
+pub enum LoaderVersionSelection {
+    Latest,
+    #[default]
+    LatestStable,
+    Exact(Versioning),
+}
+
+impl FromStr for LoaderVersionSelection {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Ok(match s {
+            "latest" => LoaderVersionSelection::Latest,
+            "latest-stable" => LoaderVersionSelection::LatestStable,
+            _ => LoaderVersionSelection::Exact(
+                Versioning::new(s).ok_or_else(|| anyhow::anyhow!("Invalid loader version"))?,
+            ),
+        })
+    }
+}
+
+/// What to do with modpack files the server may run without.
+#[derive(Clone, Copy, Default, Debug)]
+pub enum OptionalFiles {
+    Include,
+    Exclude,
+    #[default]
+    Prompt,
+}
+
+impl FromStr for OptionalFiles {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Ok(match s {
+            "include" => OptionalFiles::Include,
+            "exclude" => OptionalFiles::Exclude,
+            "prompt" => OptionalFiles::Prompt,
+            _ => anyhow::bail!("Invalid optional files selection"),
         })
     }
 }
 
 #[derive(Args, Debug)]
 pub struct InitArgs {
-    /// Path to the modpack file to import
-    #[arg(value_name = "FILE")]
-    pub file: String,
+    /// Modpack to import: a .mrpack or CurseForge zip, a packwiz directory or a packwiz pack.toml URL
+    #[arg(value_name = "FILE", conflicts_with = "minecraft_version")]
+    pub file: Option<String>,
+
+    /// Minecraft version to install when no modpack file is given
+    #[arg(
+        long = "version",
+        value_name = "VERSION",
+        required_unless_present_any = ["file", "from_bundle"]
+    )]
+    pub minecraft_version: Option<Versioning>,
+
+    /// Install from a bundle created by `feather bundle create`, without network access
+    #[arg(
+        long,
+        value_name = "BUNDLE",
+        conflicts_with_all = ["file", "minecraft_version"]
+    )]
+    pub from_bundle: Option<PathBuf>,
+
+    /// Name of the server instance, defaults to a hash of the installed profile
+    #[arg(long, value_parser = instance::parse_name)]
+    pub name: Option<String>,
+
+    /// Port the server listens on, defaults to the first port no other server uses
+    #[arg(long)]
+    pub port: Option<u16>,
+
+    /// Reinstall over an existing instance of the same name, keeping its worlds
+    #[arg(long)]
+    pub force: bool,
+
+    /// Enable RCON so `feather stop` can save the worlds before stopping. RCON listens on the
+    /// same interfaces as the server, so firewall its port or set server-ip
+    #[arg(long)]
+    pub rcon: bool,
+
+    /// Mod loader to install on top of the Minecraft server
+    #[arg(long, requires = "minecraft_version")]
+    pub loader: Option<LoaderType>,
+
+    /// Mod loader version.
+    /// [possible values: latest, latest-stable, <version>]
+    #[arg(long, requires = "loader", default_value = "latest-stable")]
+    pub loader_version: LoaderVersionSelection,
+
+    /// Optional server-side modpack files to install.
+    /// "prompt" asks interactively and includes everything when not attached to a terminal.
+    /// [possible values: include, exclude, prompt]
+    #[arg(long, default_value = "prompt")]
+    pub optional_files: OptionalFiles,
+
+    /// Maximum number of modpack files downloaded at the same time
+    #[arg(long, default_value_t = 8)]
+    pub concurrent_downloads: usize,
 
     /// Path to the working directory
     #[arg(long, default_value = ".minecraft")]
     pub working_dir: String,
 
+    /// Base URL of the CurseForge API, used to resolve CurseForge modpack files
+    #[arg(
+        long,
+        env = "CURSEFORGE_API_URL",
+        default_value = "https://api.curseforge.com/"
+    )]
+    pub curseforge_api_url: Url,
+
+    /// CurseForge API key, required by the official CurseForge API
+    #[arg(long, env = "CURSEFORGE_API_KEY", hide_env_values = true)]
+    pub curseforge_api_key: Option<String>,
+
     /// Path to the custom Java executable.
-    /// Can be "auto", "system", or a path to a Java executable.
+    /// Can be "auto", "system", or a path to a Java executable or a Java home directory.
     /// If provided by the environment variable JAVA_HOME, it will be used instead.
     /// [possible values: auto, system, <path>]
     #[arg(long, value_enum, env = "JAVA_HOME", default_value = "auto")]
@@ -71,10 +207,190 @@ pub struct InitArgs {
     pub java_args: Vec<String>,
 }
 
+#[derive(Args, Debug)]
+pub struct ProxyArgs {
+    /// Proxy software to install.
+    /// [possible values: velocity, waterfall, bungeecord]
+    #[arg(value_name = "KIND")]
+    pub kind: ProxyKind,
+
+    /// Proxy version to install, defaults to the newest one
+    #[arg(long)]
+    pub version: Option<Versioning>,
+
+    /// Port the proxy listens on
+    #[arg(long, default_value_t = 25565)]
+    pub port: u16,
+
+    /// Provide custom Java arguments to execute the proxy with
+    #[arg(long, default_values_t = [
+        "-XX:+UseG1GC".to_string(),
+        "-XX:G1HeapRegionSize=4M".to_string(),
+        "-XX:+UnlockExperimentalVMOptions".to_string(),
+        "-XX:+ParallelRefProcEnabled".to_string(),
+        "-XX:+AlwaysPreTouch".to_string(),
+        "-XX:MaxInlineLevel=15".to_string()
+    ])]
+    pub java_args: Vec<String>,
+}
+
+#[derive(Subcommand, Debug)]
+pub enum CacheCommand {
+    #[command(about = "List cached files and how many servers use each")]
+    List,
+    #[command(about = "Show the number of cached files and their total size")]
+    Size,
+    #[command(about = "Remove cached files that no server uses anymore")]
+    Gc,
+    #[command(about = "Rehash cached files and report corrupted ones")]
+    Verify,
+}
+
+#[derive(Args, Debug)]
+pub struct CacheArgs {
+    #[command(subcommand)]
+    pub command: CacheCommand,
+}
+
+#[derive(Subcommand, Debug)]
+pub enum JavaCommand {
+    #[command(about = "List installed Java versions, the JDKs they point at and their sizes")]
+    List,
+    #[command(about = "Install the newest Temurin JDK of a Java version")]
+    Install {
+        /// Java feature release to install, e.g. 21
+        #[arg(value_name = "VERSION")]
+        version: JavaVersion,
+    },
+    #[command(about = "Remove JDKs that no Java version or server uses anymore")]
+    Prune,
+    #[command(about = "Switch a server to another Java version")]
+    Use {
+        /// Instance name, or a path to a server directory
+        #[arg(value_name = "SERVER")]
+        server: PathBuf,
+
+        /// Java feature release to run the server with, installed when missing
+        #[arg(value_name = "VERSION")]
+        version: JavaVersion,
+    },
+}
+
+#[derive(Args, Debug)]
+pub struct JavaArgs {
+    #[command(subcommand)]
+    pub command: JavaCommand,
+}
+
+#[derive(Args, Debug)]
+pub struct InstanceArgs {
+    /// Instance name, see `feather list`
+    #[arg(value_name = "INSTANCE")]
+    pub name: String,
+}
+
+#[derive(Args, Debug)]
+pub struct StopArgs {
+    /// Instance name, see `feather list`
+    #[arg(value_name = "INSTANCE")]
+    pub name: String,
+
+    /// Seconds to wait for the server to save its worlds before sending SIGTERM
+    #[arg(long, default_value_t = 60)]
+    pub timeout: u64,
+}
+
+#[derive(Args, Debug)]
+pub struct StatusArgs {
+    /// Instance name, see `feather list`
+    #[arg(value_name = "INSTANCE")]
+    pub name: String,
+
+    /// Number of log lines to show
+    #[arg(short = 'n', long, default_value_t = 10)]
+    pub lines: usize,
+}
+
+#[derive(Args, Debug)]
+pub struct RunArgs {
+    /// Instance name, see `feather list`
+    #[arg(value_name = "INSTANCE")]
+    pub name: String,
+
+    /// Restart the server when it crashes, backing off when it keeps crashing
+    #[arg(long)]
+    pub restart: bool,
+
+    /// Seconds to wait for the server to save its worlds on SIGTERM before terminating it
+    #[arg(long, default_value_t = 60)]
+    pub timeout: u64,
+}
+
+#[derive(Args, Debug)]
+pub struct BundleCreateArgs {
+    #[command(flatten)]
+    pub init: InitArgs,
+
+    /// Where to write the bundle
+    #[arg(short, long, default_value = "feather-bundle.tar.gz")]
+    pub output: PathBuf,
+}
+
+#[derive(Subcommand, Debug)]
+pub enum BundleCommand {
+    #[command(about = "Resolve everything an install needs into one tarball for offline hosts")]
+    Create(Box<BundleCreateArgs>),
+}
+
+#[derive(Args, Debug)]
+pub struct BundleArgs {
+    #[command(subcommand)]
+    pub command: BundleCommand,
+}
+
 #[derive(Subcommand)]
 pub enum Commands {
     #[command(name = "init", about = "Initialize a new Feather server")]
-    Init(InitArgs),
+    Init(Box<InitArgs>),
+    #[command(
+        name = "proxy",
+        about = "Initialize a proxy in front of the Feather servers"
+    )]
+    Proxy(ProxyArgs),
+    #[command(name = "cache", about = "Inspect and clean the shared download cache")]
+    Cache(CacheArgs),
+    #[command(name = "bundle", about = "Create bundles for offline installs")]
+    Bundle(BundleArgs),
+    #[command(name = "java", about = "Manage the JDKs servers run on")]
+    Java(JavaArgs),
+    #[command(name = "list", about = "List installed server instances")]
+    List,
+    #[command(name = "start", about = "Start a server instance")]
+    Start(InstanceArgs),
+    #[command(
+        name = "stop",
+        about = "Save the worlds and stop a server instance from inside the game"
+    )]
+    Stop(StopArgs),
+    #[command(
+        name = "restart",
+        about = "Stop a server instance gracefully and start it again"
+    )]
+    Restart(StopArgs),
+    #[command(
+        name = "status",
+        about = "Show whether a server instance runs, its resources and its latest logs"
+    )]
+    Status(StatusArgs),
+    #[command(name = "enable", about = "Start a server instance on boot")]
+    Enable(InstanceArgs),
+    #[command(name = "disable", about = "Stop starting a server instance on boot")]
+    Disable(InstanceArgs),
+    #[command(
+        name = "run",
+        about = "Run a server instance in the foreground, for hosts without systemd"
+    )]
+    Run(RunArgs),
 }
 
 pub fn parse() -> Cli {
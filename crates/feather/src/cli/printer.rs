@@ -37,7 +37,6 @@ impl Printer {
     }
 }
 
-
 pub enum Stdout {
     Enabled,
     Disabled,
@@ -47,12 +46,11 @@ impl std::fmt::Write for Stdout {
     fn write_str(&mut self, s: &str) -> std::fmt::Result {
         match self {
             Stdout::Enabled => println!("{}", s),
-            Stdout::Disabled => {},
+            Stdout::Disabled => {}
         }
 
         Ok(())
     }
-    
 }
 pub enum Stderr {
     Enabled,
@@ -63,7 +61,7 @@ impl std::fmt::Write for Stderr {
     fn write_str(&mut self, s: &str) -> std::fmt::Result {
         match self {
             Stderr::Enabled => eprintln!("{}", s),
-            Stderr::Disabled => {},
+            Stderr::Disabled => {}
         }
 
         Ok(())
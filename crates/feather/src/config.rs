@@ -1,5 +1,12 @@
+use crate::{minecraft::ServerLaunch, proxy::Forwarding};
 use anyhow::{Context, Result};
-use std::path::{Path, PathBuf};
+use serde_yaml::{Mapping, Value};
+use std::{
+    fs::Permissions,
+    io::Read,
+    os::unix::fs::PermissionsExt,
+    path::{Path, PathBuf},
+};
 
 pub struct ConfigGenerator {
     server_dir: PathBuf,
@@ -29,7 +36,7 @@ impl ConfigGenerator {
         &self,
         java_executable: &Path,
         java_args: &[String],
-        server_jar: &str,
+        launch: &ServerLaunch,
     ) -> Result<()> {
         tracing::info!("Creating Feather environment file...");
 
@@ -37,10 +44,10 @@ impl ConfigGenerator {
         let java_args_str = java_args.join(" ");
 
         let env_content = format!(
-            "JAVA_EXECUTABLE={}\nJAVA_ARGS={}\nSERVER_JAR={}\n",
+            "JAVA_EXECUTABLE={}\nJAVA_ARGS={}\nSERVER_ARGS={}\n",
             java_executable.display(),
             java_args_str,
-            server_jar
+            launch
         );
 
         std::fs::write(&env_path, env_content).with_context(|| {
@@ -56,4 +63,267 @@ impl ConfigGenerator {
         );
         Ok(())
     }
+
+    pub fn read_java_executable(&self) -> Result<Option<PathBuf>> {
+        if !self.server_dir.join("feather.env").exists() {
+            return Ok(None);
+        }
+
+        Ok(self.read_feather_env("JAVA_EXECUTABLE")?.map(PathBuf::from))
+    }
+
+    pub fn read_feather_env(&self, key: &str) -> Result<Option<String>> {
+        let env_path = self.server_dir.join("feather.env");
+
+        let content = std::fs::read_to_string(&env_path).with_context(|| {
+            format!(
+                "Failed to read Feather environment file: {}",
+                env_path.display()
+            )
+        })?;
+
+        Ok(content.lines().find_map(|line| {
+            let (k, v) = line.split_once('=')?;
+            (k == key).then(|| v.to_string())
+        }))
+    }
+
+    /// Points `feather.env` at another Java executable, keeping the launch arguments.
+    pub fn set_java_executable(&self, java_executable: &Path) -> Result<()> {
+        let env_path = self.server_dir.join("feather.env");
+
+        let content = std::fs::read_to_string(&env_path).with_context(|| {
+            format!(
+                "Failed to read Feather environment file: {}",
+                env_path.display()
+            )
+        })?;
+
+        let entry = format!("JAVA_EXECUTABLE={}", java_executable.display());
+        let mut lines = content
+            .lines()
+            .filter(|line| !line.starts_with("JAVA_EXECUTABLE="))
+            .map(str::to_string)
+            .collect::<Vec<_>>();
+        lines.insert(0, entry);
+
+        std::fs::write(&env_path, lines.join("\n") + "\n").with_context(|| {
+            format!(
+                "Failed to write Feather environment file: {}",
+                env_path.display()
+            )
+        })?;
+
+        tracing::debug!(
+            "Feather environment file updated at: {}",
+            env_path.display()
+        );
+        Ok(())
+    }
+
+    pub fn read_server_property(&self, key: &str) -> Result<Option<String>> {
+        let properties_path = self.server_dir.join("server.properties");
+        if !properties_path.exists() {
+            return Ok(None);
+        }
+
+        let content = std::fs::read_to_string(&properties_path).with_context(|| {
+            format!(
+                "Failed to read server properties: {}",
+                properties_path.display()
+            )
+        })?;
+
+        Ok(content.lines().find_map(|line| {
+            let (k, v) = line.split_once('=')?;
+            (k.trim() == key).then(|| v.trim().to_string())
+        }))
+    }
+
+    /// Sets keys in `server.properties`, keeping every other line as the server wrote it.
+    ///
+    /// The file holds the RCON password, so it is only readable by its owner.
+    pub fn set_server_properties(&self, properties: &[(&str, String)]) -> Result<()> {
+        let properties_path = self.server_dir.join("server.properties");
+
+        let content = if properties_path.exists() {
+            std::fs::read_to_string(&properties_path).with_context(|| {
+                format!(
+                    "Failed to read server properties: {}",
+                    properties_path.display()
+                )
+            })?
+        } else {
+            String::new()
+        };
+
+        let mut lines = content.lines().map(str::to_string).collect::<Vec<_>>();
+
+        for (key, value) in properties {
+            let entry = format!("{key}={value}");
+            let existing = lines
+                .iter_mut()
+                .find(|line| line.split_once('=').is_some_and(|(k, _)| k.trim() == *key));
+
+            match existing {
+                Some(line) => *line = entry,
+                None => lines.push(entry),
+            }
+        }
+
+        std::fs::write(&properties_path, lines.join("\n") + "\n")
+            .and_then(|()| {
+                std::fs::set_permissions(&properties_path, Permissions::from_mode(0o600))
+            })
+            .with_context(|| {
+                format!(
+                    "Failed to write server properties: {}",
+                    properties_path.display()
+                )
+            })?;
+
+        tracing::debug!(
+            "Server properties updated at: {}",
+            properties_path.display()
+        );
+        Ok(())
+    }
+
+    /// Turns on RCON on `rcon_port` for `feather stop`, keeping the password of an earlier
+    /// install.
+    pub fn enable_rcon(&self, rcon_port: u16) -> Result<()> {
+        let rcon_password = match self.read_server_property("rcon.password")? {
+            Some(password) if !password.is_empty() => password,
+            _ => random_secret().context("Failed to generate RCON password")?,
+        };
+
+        // Minecraft binds RCON to `server-ip`, which is every interface unless set.
+        if self
+            .read_server_property("server-ip")?
+            .is_none_or(|ip| ip.trim().is_empty())
+        {
+            tracing::warn!(
+                "RCON listens on port {} on all interfaces, block it in the firewall or set server-ip",
+                rcon_port
+            );
+        }
+
+        self.set_server_properties(&[
+            ("enable-rcon", "true".to_string()),
+            ("rcon.port", rcon_port.to_string()),
+            ("rcon.password", rcon_password),
+        ])
+    }
+
+    /// RCON port and password, `None` when RCON is turned off.
+    pub fn read_rcon(&self) -> Result<Option<(u16, String)>> {
+        if self.read_server_property("enable-rcon")?.as_deref() != Some("true") {
+            return Ok(None);
+        }
+
+        let port = self
+            .read_server_property("rcon.port")?
+            .and_then(|port| port.parse::<u16>().ok());
+        let password = self
+            .read_server_property("rcon.password")?
+            .filter(|password| !password.is_empty());
+
+        Ok(port.zip(password))
+    }
+
+    /// Points Paper (and its forks) at the proxy's player info forwarding.
+    pub fn configure_paper_forwarding(&self, forwarding: &Forwarding) -> Result<()> {
+        tracing::info!("Configuring Paper proxy forwarding...");
+
+        let paper_global = self.server_dir.join("config").join("paper-global.yml");
+
+        match forwarding {
+            Forwarding::Velocity { secret } => {
+                update_yaml(&paper_global, |root| {
+                    set_yaml(root, &["proxies", "velocity", "enabled"], Value::Bool(true));
+                    set_yaml(
+                        root,
+                        &["proxies", "velocity", "online-mode"],
+                        Value::Bool(true),
+                    );
+                    set_yaml(
+                        root,
+                        &["proxies", "velocity", "secret"],
+                        Value::String(secret.clone()),
+                    );
+                })?;
+
+                // The secret lets anyone log in to the backend as any player.
+                std::fs::set_permissions(&paper_global, Permissions::from_mode(0o600))
+                    .with_context(|| format!("Failed to restrict {}", paper_global.display()))?;
+            }
+            Forwarding::BungeeCord => {
+                update_yaml(&self.server_dir.join("spigot.yml"), |root| {
+                    set_yaml(root, &["settings", "bungeecord"], Value::Bool(true));
+                })?;
+                update_yaml(&paper_global, |root| {
+                    set_yaml(
+                        root,
+                        &["proxies", "bungee-cord", "online-mode"],
+                        Value::Bool(true),
+                    );
+                })?;
+            }
+        }
+
+        Ok(())
+    }
+}
+
+/// 24 random bytes from the kernel, hex encoded.
+pub fn random_secret() -> Result<String> {
+    let mut bytes = [0u8; 24];
+    std::fs::File::open("/dev/urandom").and_then(|mut urandom| urandom.read_exact(&mut bytes))?;
+
+    Ok(bytes.iter().map(|b| format!("{b:02x}")).collect())
+}
+
+/// Edits a YAML config in place, creating it if the server has not generated it yet.
+pub fn update_yaml(path: &Path, edit: impl FnOnce(&mut Mapping)) -> Result<()> {
+    let mut root = if path.exists() {
+        let content = std::fs::read_to_string(path)
+            .with_context(|| format!("Failed to read {}", path.display()))?;
+        serde_yaml::from_str::<Option<Mapping>>(&content)
+            .with_context(|| format!("Failed to parse {}", path.display()))?
+            .unwrap_or_default()
+    } else {
+        Mapping::new()
+    };
+
+    edit(&mut root);
+
+    if let Some(parent) = path.parent() {
+        std::fs::create_dir_all(parent)
+            .with_context(|| format!("Failed to create directory: {}", parent.display()))?;
+    }
+
+    let content = serde_yaml::to_string(&root)?;
+    std::fs::write(path, content).with_context(|| format!("Failed to write {}", path.display()))
+}
+
+/// Sets a nested key, replacing any non-mapping values along the way.
+pub fn set_yaml(root: &mut Mapping, keys: &[&str], value: Value) {
+    let Some((last, parents)) = keys.split_last() else {
+        return;
+    };
+
+    let mut current = root;
+    for key in parents {
+        let entry = current
+            .entry(Value::String(key.to_string()))
+            .or_insert_with(|| Value::Mapping(Mapping::new()));
+
+        if !entry.is_mapping() {
+            *entry = Value::Mapping(Mapping::new());
+        }
+
+        current = entry.as_mapping_mut().unwrap();
+    }
+
+    current.insert(Value::String(last.to_string()), value);
 }
@@ -1,60 +1,361 @@
 use crate::{
-    cli::InitArgs, config::ConfigGenerator, java::JavaInstaller, minecraft::MinecraftInstaller,
-    modpack::MinecraftProfile,
+    bundle::{self, BundleLock, JAVA_DIR, SERVER_DIR},
+    cli::{InitArgs, JavaSelection, OptionalFiles},
+    config::ConfigGenerator,
+    instance::Instance,
+    java::{self, JavaInstaller, JavaVersion},
+    minecraft::{MinecraftInstaller, ServerLaunch},
+    modpack::{CurseForgeClient, LoaderType, MinecraftProfile, safe_relative_path},
+    network::Downloader,
+    proxy, systemd,
 };
-use anyhow::{Context, Result};
-use std::path::{Path, PathBuf};
+use anyhow::{Context, Result, anyhow};
+use std::{
+    collections::BTreeSet,
+    path::{Path, PathBuf},
+    process::Stdio,
+    time::Duration,
+};
+
+const DEFAULT_PORT: u16 = 25565;
+const DEFAULT_RCON_PORT: u16 = 25575;
+/// Upper bound for the first start of a server while creating a bundle, which downloads the
+/// vanilla jar and libraries.
+const BOOTSTRAP_TIMEOUT: Duration = Duration::from_secs(600);
 
 pub struct MinecraftServerInstaller {
     profile: MinecraftProfile,
+    name: String,
     java_cache_dir: PathBuf,
+    minecraft_servers_dir: PathBuf,
+    proxies_dir: PathBuf,
     server_dir: PathBuf,
+    templates_dir: PathBuf,
+    port: Option<u16>,
+    rcon: bool,
+    java: JavaSelection,
     java_args: Vec<String>,
+    curseforge: CurseForgeClient,
+    optional_files: OptionalFiles,
+    downloader: Downloader,
 }
 
 impl MinecraftServerInstaller {
     pub fn new(
         profile: MinecraftProfile,
         args: &InitArgs,
+        downloader: &Downloader,
         java_cache_dir: &Path,
         minecraft_servers_dir: &Path,
+        proxies_dir: &Path,
+        templates_dir: &Path,
     ) -> Self {
-        let server_dir = minecraft_servers_dir.join(profile.hash());
+        // Unnamed servers keep the directory name earlier versions derived from the profile.
+        let name = args.name.clone().unwrap_or_else(|| profile.hash());
+        let server_dir = minecraft_servers_dir.join(&name);
 
         Self {
             profile,
+            name,
             java_cache_dir: java_cache_dir.to_path_buf(),
+            minecraft_servers_dir: minecraft_servers_dir.to_path_buf(),
+            proxies_dir: proxies_dir.to_path_buf(),
             server_dir,
+            templates_dir: templates_dir.to_path_buf(),
+            port: args.port,
+            rcon: args.rcon,
+            java: args.java.clone(),
             java_args: args.java_args.clone(),
+            curseforge: CurseForgeClient::new(
+                args.curseforge_api_url.clone(),
+                args.curseforge_api_key.clone(),
+            ),
+            optional_files: args.optional_files,
+            downloader: downloader.clone(),
         }
     }
 
-    pub async fn install(&self) -> Result<()> {
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    pub async fn install(&self) -> Result<Instance> {
         tracing::info!("Starting Minecraft server installation...");
 
+        create_feather_user().context("Failed to create feather user")?;
+
+        let (_, java_executable, launch) = self.stage(&self.java).await?;
+        let instance = self.finish(&java_executable, &launch)?;
+
+        tracing::info!("Minecraft server installation completed successfully");
+        Ok(instance)
+    }
+
+    /// Installs a full server into a staging area and packs it into a bundle at `output`.
+    ///
+    /// The installer must have been created with staging directories for Java and servers.
+    pub async fn create_bundle(&self, output: &Path) -> Result<()> {
+        tracing::info!("Creating bundle...");
+
+        if !matches!(self.java, JavaSelection::Auto) {
+            tracing::warn!(
+                "Bundles ship their own JDK, ignoring --java {:?}",
+                self.java
+            );
+        }
+
+        let (java_version, java_executable, launch) = self.stage(&JavaSelection::Auto).await?;
+
+        if self.profile.loader.as_ref().is_some_and(|loader| {
+            matches!(
+                loader.name,
+                LoaderType::Fabric | LoaderType::Paper | LoaderType::Purpur | LoaderType::Folia
+            )
+        }) {
+            self.bootstrap(&java_executable, &launch)
+                .await
+                .context("Failed to prepare the server for offline installs")?;
+        }
+
+        let jdk_dir = JavaInstaller::new(&self.java_cache_dir).installed_jdk_dir(&java_version)?;
+        let jdk_dir = jdk_dir.strip_prefix(&self.java_cache_dir)?.to_path_buf();
+        let jdk_root_name = jdk_dir
+            .components()
+            .next()
+            .context("JDK is not inside the Java cache")?;
+        let jdk_root = self.java_cache_dir.join(jdk_root_name);
+
+        let mut files = bundle::hash_files(&jdk_root, &Path::new(JAVA_DIR).join(jdk_root_name))?;
+        files.extend(bundle::hash_files(&self.server_dir, Path::new(SERVER_DIR))?);
+
+        let lock = BundleLock {
+            profile: self.profile.clone(),
+            java_version,
+            jdk_dir,
+            launch,
+            files,
+        };
+
+        bundle::write(&lock, &jdk_root, &self.server_dir, output)
+            .with_context(|| format!("Failed to write bundle: {}", output.display()))?;
+
+        tracing::info!("Bundle with {} files written", lock.files.len());
+        Ok(())
+    }
+
+    /// Starts the server once so it fetches what it would otherwise download on its first start:
+    /// Fabric's launcher pulls the vanilla jar and libraries, Paperclip the vanilla jar it patches.
+    /// Without an accepted EULA the server exits right after loading, before creating a world.
+    async fn bootstrap(&self, java_executable: &Path, launch: &ServerLaunch) -> Result<()> {
+        tracing::info!("Starting the server once to download its libraries...");
+
+        let eula_path = self.server_dir.join("eula.txt");
+        if eula_path.exists() {
+            std::fs::remove_file(&eula_path)?;
+        }
+
+        let output = tokio::time::timeout(
+            BOOTSTRAP_TIMEOUT,
+            tokio::process::Command::new(java_executable)
+                .args(launch.to_string().split_whitespace())
+                .arg("nogui")
+                .current_dir(&self.server_dir)
+                .stdin(Stdio::null())
+                .kill_on_drop(true)
+                .output(),
+        )
+        .await
+        .context("Server did not finish its first start in time")?
+        .context("Failed to start the server")?;
+
+        // The EULA check comes after everything is downloaded, so reaching it means success.
+        if !eula_path.exists() {
+            return Err(anyhow!(
+                "Server exited with {} before reaching the EULA check:\n{}",
+                output.status,
+                String::from_utf8_lossy(&output.stdout)
+            ));
+        }
+
+        // Defaults written on the way would otherwise pin the port of every install to 25565.
+        for file in ["eula.txt", "server.properties"] {
+            let path = self.server_dir.join(file);
+            if path.exists() {
+                std::fs::remove_file(&path)?;
+            }
+        }
+        let logs_dir = self.server_dir.join("logs");
+        if logs_dir.exists() {
+            std::fs::remove_dir_all(&logs_dir)?;
+        }
+
+        Ok(())
+    }
+
+    /// Installs from a bundle unpacked into `staging` by [`bundle::extract`], without network
+    /// access. `staging` must be on the same filesystem as the install directories.
+    pub fn install_bundle(&self, staging: &Path, lock: &BundleLock) -> Result<Instance> {
+        tracing::info!("Installing Minecraft server from bundle...");
+
         self.create_directories()
             .context("Failed to create directories")?;
 
-        self.create_user()
-            .context("Failed to create feather user")?;
+        create_feather_user().context("Failed to create feather user")?;
+
+        // The lockfile comes from the bundle, so its JDK path must stay inside the Java cache.
+        let jdk_dir = safe_relative_path(&lock.jdk_dir.to_string_lossy())
+            .context("Lockfile has an invalid JDK path")?;
+        let jdk_root_name = jdk_dir
+            .components()
+            .next()
+            .context("Lockfile has an empty JDK path")?;
+        let jdk_root = self.java_cache_dir.join(jdk_root_name);
+
+        if !jdk_root.exists() {
+            std::fs::rename(staging.join(JAVA_DIR).join(jdk_root_name), &jdk_root)
+                .with_context(|| format!("Failed to install JDK to {}", jdk_root.display()))?;
+        }
+
+        let java_executable = JavaInstaller::new(&self.java_cache_dir)
+            .link_version(&lock.java_version, &self.java_cache_dir.join(&jdk_dir))?;
+
+        for entry in
+            std::fs::read_dir(staging.join(SERVER_DIR)).context("Bundle has no server directory")?
+        {
+            let entry = entry?;
+            let target = self.server_dir.join(entry.file_name());
+
+            if target.is_dir() && !target.is_symlink() {
+                std::fs::remove_dir_all(&target)?;
+            } else if target.symlink_metadata().is_ok() {
+                std::fs::remove_file(&target)?;
+            }
+
+            std::fs::rename(entry.path(), &target)
+                .with_context(|| format!("Failed to install {}", target.display()))?;
+        }
+
+        let instance = self.finish(&java_executable, &lock.launch)?;
+
+        tracing::info!("Minecraft server installation completed successfully");
+        Ok(instance)
+    }
+
+    /// Downloads Java and the server without touching system configuration.
+    async fn stage(&self, java: &JavaSelection) -> Result<(JavaVersion, PathBuf, ServerLaunch)> {
+        self.create_directories()
+            .context("Failed to create directories")?;
+
+        let java_version = JavaInstaller::new(&self.java_cache_dir)
+            .determine_java_version(&self.profile.version)
+            .await;
 
         let java_executable = self
-            .install_java()
+            .install_java(java, java_version)
             .await
             .context("Failed to install Java")?;
 
-        self.install_minecraft_server()
+        let launch = self
+            .install_minecraft_server(&java_executable)
             .await
             .context("Failed to install Minecraft server")?;
 
-        self.create_config_files(&java_executable)
+        Ok((java_version, java_executable, launch))
+    }
+
+    fn finish(&self, java_executable: &Path, launch: &ServerLaunch) -> Result<Instance> {
+        let port = self.assign_port()?;
+
+        self.create_config_files(java_executable, launch, port)
             .context("Failed to create configuration files")?;
 
-        self.setup_systemd()
-            .context("Failed to setup systemd service")?;
+        // The server runs as feather and must be able to read its private config and save worlds.
+        chown_to_feather(&self.server_dir).context("Failed to hand the server to feather")?;
 
-        tracing::info!("Minecraft server installation completed successfully");
-        Ok(())
+        if systemd::is_running() {
+            self.setup_systemd()
+                .context("Failed to setup systemd service")?;
+        } else {
+            tracing::warn!(
+                "systemd is not running, skipping the service. Run the server with `feather run {}`.",
+                self.name
+            );
+        }
+
+        Ok(Instance {
+            name: self.name.clone(),
+            profile: self.profile.clone(),
+            dir: self.server_dir.clone(),
+            java: java_executable.to_path_buf(),
+            port,
+        })
+    }
+
+    /// Keeps the port a reinstalled server already listens on, otherwise takes the first port
+    /// no other server or proxy uses.
+    fn assign_port(&self) -> Result<u16> {
+        if let Some(port) = self.port {
+            if proxy::proxy_ports(&self.proxies_dir)?.contains(&port) {
+                return Err(anyhow!("Port {} is already used by a proxy", port));
+            }
+            return Ok(port);
+        }
+
+        if let Some(port) = ConfigGenerator::new(&self.server_dir)
+            .read_server_property("server-port")?
+            .and_then(|port| port.parse::<u16>().ok())
+        {
+            return Ok(port);
+        }
+
+        let used = self.used_ports()?;
+        (DEFAULT_PORT..=u16::MAX)
+            .find(|port| !used.contains(port))
+            .context("No free port left for the server")
+    }
+
+    /// Keeps the RCON port of a reinstalled server unless something else took it, otherwise
+    /// takes the first free port from the vanilla default up.
+    fn assign_rcon_port(&self, port: u16) -> Result<u16> {
+        let mut used = self.used_ports()?;
+        used.insert(port);
+
+        if let Some(rcon_port) = ConfigGenerator::new(&self.server_dir)
+            .read_server_property("rcon.port")?
+            .and_then(|port| port.parse::<u16>().ok())
+            .filter(|rcon_port| !used.contains(rcon_port))
+        {
+            return Ok(rcon_port);
+        }
+
+        (DEFAULT_RCON_PORT..=u16::MAX)
+            .find(|port| !used.contains(port))
+            .context("No free port left for RCON")
+    }
+
+    /// Game and RCON ports of every other server, and the ports proxies listen on.
+    fn used_ports(&self) -> Result<BTreeSet<u16>> {
+        let mut used = proxy::proxy_ports(&self.proxies_dir)?;
+
+        for entry in std::fs::read_dir(&self.minecraft_servers_dir)? {
+            let dir = entry?.path();
+            if dir == self.server_dir || !dir.join("feather.env").exists() {
+                continue;
+            }
+
+            let config = ConfigGenerator::new(&dir);
+            let port = config
+                .read_server_property("server-port")?
+                .and_then(|port| port.parse::<u16>().ok())
+                .unwrap_or(DEFAULT_PORT);
+            used.insert(port);
+
+            if let Some((rcon_port, _)) = config.read_rcon()? {
+                used.insert(rcon_port);
+            }
+        }
+
+        Ok(used)
     }
 
     fn create_directories(&self) -> Result<()> {
@@ -77,54 +378,78 @@ impl MinecraftServerInstaller {
         Ok(())
     }
 
-    fn create_user(&self) -> Result<()> {
-        tracing::info!("Creating feather user...");
-
-        let output = std::process::Command::new("id")
-            .arg("feather")
-            .output()
-            .context("Failed to check if feather user exists")?;
-
-        if !output.status.success() {
-            tracing::info!("Creating feather user...");
-            let status = std::process::Command::new("sudo")
-                .args(["useradd", "-r", "-s", "/bin/false", "feather"])
-                .status()
-                .context("Failed to create feather user")?;
-
-            if !status.success() {
-                return Err(anyhow::anyhow!("Failed to create feather user"));
+    async fn install_java(
+        &self,
+        java: &JavaSelection,
+        java_version: JavaVersion,
+    ) -> Result<PathBuf> {
+        let java_executable = match java {
+            JavaSelection::Auto => {
+                tracing::info!("Installing Java...");
+
+                return JavaInstaller::new(&self.java_cache_dir)
+                    .install(java_version)
+                    .await;
             }
-        } else {
-            tracing::info!("Feather user already exists");
+            JavaSelection::System => java::find_system_java()?,
+            JavaSelection::Path(path) => java::java_executable_at(path)?,
+        };
+
+        let major = java::probe_java_version(&java_executable)?;
+        tracing::info!("Using Java {} at {}", major, java_executable.display());
+
+        if major < java_version.0 {
+            return Err(anyhow!(
+                "{} is Java {}, but Minecraft {} needs Java {} or newer",
+                java_executable.display(),
+                major,
+                self.profile.version,
+                java_version
+            ));
         }
 
-        Ok(())
-    }
-
-    async fn install_java(&self) -> Result<PathBuf> {
-        tracing::info!("Installing Java...");
-
-        let java_installer = JavaInstaller::new(&self.java_cache_dir);
-        let java_version = java_installer.determine_java_version(&self.profile.version);
+        if java_version == JavaVersion(8) && major > 8 {
+            tracing::warn!(
+                "Minecraft {} is made for Java 8 and may not start on Java {}",
+                self.profile.version,
+                major
+            );
+        }
 
-        java_installer.install(java_version).await
+        Ok(java_executable)
     }
 
-    async fn install_minecraft_server(&self) -> Result<()> {
+    async fn install_minecraft_server(&self, java_executable: &Path) -> Result<ServerLaunch> {
         tracing::info!("Installing Minecraft server...");
 
-        let minecraft_installer = MinecraftInstaller::new(&self.server_dir);
+        let minecraft_installer = MinecraftInstaller::new(
+            &self.server_dir,
+            java_executable,
+            &self.curseforge,
+            self.optional_files,
+            &self.downloader,
+        );
         minecraft_installer.install(&self.profile).await
     }
 
-    fn create_config_files(&self, java_executable: &Path) -> Result<()> {
+    fn create_config_files(
+        &self,
+        java_executable: &Path,
+        launch: &ServerLaunch,
+        port: u16,
+    ) -> Result<()> {
         tracing::info!("Creating configuration files...");
 
         let config_generator = ConfigGenerator::new(&self.server_dir);
 
         config_generator.create_eula_file()?;
-        config_generator.create_feather_env_file(java_executable, &self.java_args, "server.jar")?;
+        config_generator.create_feather_env_file(java_executable, &self.java_args, launch)?;
+        config_generator.set_server_properties(&[("server-port", port.to_string())])?;
+
+        // Reinstalls keep RCON on when it was turned on before.
+        if self.rcon || config_generator.read_rcon()?.is_some() {
+            config_generator.enable_rcon(self.assign_rcon_port(port)?)?;
+        }
 
         Ok(())
     }
@@ -132,22 +457,86 @@ impl MinecraftServerInstaller {
     fn setup_systemd(&self) -> Result<()> {
         tracing::info!("Setting up systemd service...");
 
-        let service_content = include_str!("templates/feather.service");
-        let service_path = "/etc/systemd/system/feather.service";
+        let unit_name = systemd::unit_name(&self.name);
+        let service_content = systemd::render_server_unit(
+            &self.templates_dir,
+            &self.name,
+            &self.server_dir,
+            &self.java_args,
+        )?;
+
+        systemd::install_unit(&unit_name, &service_content)?;
+        systemd::remove_legacy_unit()?;
 
-        std::fs::write(service_path, service_content)
-            .with_context(|| format!("Failed to write systemd service file: {service_path}"))?;
+        tracing::info!("Systemd service {} configured successfully", unit_name);
+        Ok(())
+    }
+}
+
+pub fn create_feather_user() -> Result<()> {
+    tracing::info!("Creating feather user...");
 
+    let output = std::process::Command::new("id")
+        .arg("feather")
+        .output()
+        .context("Failed to check if feather user exists")?;
+
+    if !output.status.success() {
+        tracing::info!("Creating feather user...");
         let status = std::process::Command::new("sudo")
-            .args(["systemctl", "daemon-reload"])
+            .args(["useradd", "-r", "-s", "/bin/false", "feather"])
             .status()
-            .context("Failed to reload systemd daemon")?;
+            .context("Failed to create feather user")?;
 
         if !status.success() {
-            return Err(anyhow::anyhow!("Failed to reload systemd daemon"));
+            return Err(anyhow::anyhow!("Failed to create feather user"));
         }
+    } else {
+        tracing::info!("Feather user already exists");
+    }
 
-        tracing::info!("Systemd service configured successfully");
-        Ok(())
+    Ok(())
+}
+
+/// User and group id of the feather user.
+pub fn feather_ids() -> Result<(u32, u32)> {
+    let id = |flag: &str| -> Result<u32> {
+        let output = std::process::Command::new("id")
+            .args([flag, "feather"])
+            .output()
+            .context("Failed to run id")?;
+
+        if !output.status.success() {
+            return Err(anyhow!(
+                "The feather user does not exist, run `feather init` first"
+            ));
+        }
+
+        String::from_utf8_lossy(&output.stdout)
+            .trim()
+            .parse()
+            .context("Failed to parse the output of id")
+    };
+
+    Ok((id("-u")?, id("-g")?))
+}
+
+/// Recursively gives `path` to the feather user.
+///
+/// Files with more than one link are hardlinks into the shared cache and stay with their owner,
+/// otherwise every other server linking the same entry would change hands too.
+pub fn chown_to_feather(path: &Path) -> Result<()> {
+    let status = std::process::Command::new("sudo")
+        .arg("find")
+        .arg(path)
+        .args(["(", "-type", "f", "-links", "+1", ")", "-prune", "-o"])
+        .args(["-exec", "chown", "-h", "feather:feather", "{}", "+"])
+        .status()
+        .with_context(|| format!("Failed to run chown on {}", path.display()))?;
+
+    if !status.success() {
+        return Err(anyhow!("Failed to change the owner of {}", path.display()));
     }
+
+    Ok(())
 }
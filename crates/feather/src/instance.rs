@@ -0,0 +1,161 @@
+use anyhow::{Context, Result, anyhow};
+use serde::{Deserialize, Serialize};
+use std::{
+    collections::BTreeMap,
+    path::{Path, PathBuf},
+};
+
+use crate::modpack::MinecraftProfile;
+
+/// A server installed by `feather init`.
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct Instance {
+    pub name: String,
+    pub profile: MinecraftProfile,
+    pub dir: PathBuf,
+    pub java: PathBuf,
+    pub port: u16,
+}
+
+/// Every instance on this host, stored as JSON under the feather home directory.
+#[derive(Serialize, Deserialize, Debug, Default)]
+pub struct Registry {
+    instances: BTreeMap<String, Instance>,
+}
+
+impl Registry {
+    pub fn load(path: &Path) -> Result<Self> {
+        if !path.exists() {
+            return Ok(Self::default());
+        }
+
+        let content = std::fs::read(path)
+            .with_context(|| format!("Failed to read instance registry: {}", path.display()))?;
+
+        serde_json::from_slice(&content)
+            .with_context(|| format!("Failed to parse instance registry: {}", path.display()))
+    }
+
+    /// Writes the registry through a temporary file so a crash never leaves it half written.
+    pub fn save(&self, path: &Path) -> Result<()> {
+        let temp_path = path.with_extension("json.tmp");
+
+        std::fs::write(&temp_path, serde_json::to_vec_pretty(self)?).with_context(|| {
+            format!("Failed to write instance registry: {}", temp_path.display())
+        })?;
+        std::fs::rename(&temp_path, path)
+            .with_context(|| format!("Failed to write instance registry: {}", path.display()))
+    }
+
+    pub fn get(&self, name: &str) -> Result<&Instance> {
+        self.instances
+            .get(name)
+            .with_context(|| format!("Unknown instance '{name}', see `feather list`"))
+    }
+
+    pub fn get_mut(&mut self, name: &str) -> Option<&mut Instance> {
+        self.instances.get_mut(name)
+    }
+
+    /// Refuses to install over an existing instance unless `force` is set, and to reuse a port
+    /// another instance listens on.
+    pub fn check_new(&self, name: &str, port: Option<u16>, force: bool) -> Result<()> {
+        if self.instances.contains_key(name) && !force {
+            return Err(anyhow!(
+                "Instance '{}' already exists, pass --force to reinstall it",
+                name
+            ));
+        }
+
+        if let Some(port) = port
+            && let Some(other) = self
+                .instances()
+                .find(|instance| instance.port == port && instance.name != name)
+        {
+            return Err(anyhow!(
+                "Port {} is already used by instance '{}'",
+                port,
+                other.name
+            ));
+        }
+
+        Ok(())
+    }
+
+    /// Adds an instance, replacing a previous install under the same name.
+    pub fn insert(&mut self, instance: Instance) {
+        self.instances.insert(instance.name.clone(), instance);
+    }
+
+    /// Instances sorted by name.
+    pub fn instances(&self) -> impl Iterator<Item = &Instance> {
+        self.instances.values()
+    }
+}
+
+/// Instance names become directory names and systemd unit instances, so they are restricted
+/// to characters that are safe in both.
+pub fn parse_name(name: &str) -> Result<String> {
+    let valid = !name.is_empty()
+        && name.len() <= 64
+        && !name.starts_with(['-', '.'])
+        && name
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || matches!(c, '-' | '_' | '.'));
+
+    if !valid {
+        return Err(anyhow!(
+            "Invalid instance name {:?}, use up to 64 letters, digits, '-', '_' or '.'",
+            name
+        ));
+    }
+
+    Ok(name.to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use versions::Versioning;
+
+    fn registry(instances: &[(&str, u16)]) -> Registry {
+        let mut registry = Registry::default();
+        for (name, port) in instances {
+            registry.insert(Instance {
+                name: name.to_string(),
+                profile: MinecraftProfile {
+                    version: Versioning::new("1.21.1").unwrap(),
+                    loader: None,
+                    modpack: None,
+                },
+                dir: PathBuf::from("/opt/feather/servers").join(name),
+                java: PathBuf::from("/usr/bin/java"),
+                port: *port,
+            });
+        }
+        registry
+    }
+
+    #[test]
+    fn accepts_new_names_and_ports() {
+        let registry = registry(&[("survival", 25565)]);
+        assert!(registry.check_new("creative", Some(25566), false).is_ok());
+        assert!(registry.check_new("creative", None, false).is_ok());
+    }
+
+    #[test]
+    fn refuses_existing_names_without_force() {
+        let registry = registry(&[("survival", 25565)]);
+        assert!(registry.check_new("survival", None, false).is_err());
+        assert!(registry.check_new("survival", None, true).is_ok());
+    }
+
+    #[test]
+    fn refuses_ports_of_other_instances() {
+        let registry = registry(&[("survival", 25565), ("creative", 25566)]);
+        assert!(registry.check_new("lobby", Some(25566), false).is_err());
+        assert!(registry.check_new("lobby", Some(25566), true).is_err());
+        // A reinstall keeps its own port.
+        assert!(registry.check_new("creative", Some(25566), true).is_ok());
+    }
+}
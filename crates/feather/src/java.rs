@@ -1,31 +1,107 @@
+use crate::{
+    checksum::Checksum,
+    network::{self, MinecraftVersions},
+};
 use anyhow::{Context, Result, anyhow};
 use flate2::bufread::GzDecoder;
-use rustc_hash::FxHasher;
-use std::hash::Hasher;
+use serde::{Deserialize, Serialize};
 use std::{
     env::consts::{ARCH, OS},
+    ffi::OsStr,
+    fs::File,
+    io::BufReader,
     path::{Path, PathBuf},
+    str::FromStr,
 };
 use tar::Archive;
 use versions::Versioning;
 
-#[derive(Debug, Clone)]
-pub enum JavaVersion {
-    Java8,
-    Java17,
-    Java21,
-}
+/// Java feature release, e.g. 21 for Java 21.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize)]
+pub struct JavaVersion(pub u32);
 
 impl std::fmt::Display for JavaVersion {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-        match self {
-            JavaVersion::Java8 => write!(f, "8"),
-            JavaVersion::Java17 => write!(f, "17"),
-            JavaVersion::Java21 => write!(f, "21"),
-        }
+        write!(f, "{}", self.0)
     }
 }
 
+impl FromStr for JavaVersion {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        // Adoptium only publishes Java 8 and newer.
+        s.parse::<u32>()
+            .ok()
+            .filter(|major| *major >= 8)
+            .map(JavaVersion)
+            .ok_or_else(|| anyhow!("Unsupported Java version: {}", s))
+    }
+}
+
+#[derive(Deserialize)]
+struct AdoptiumAsset {
+    binary: AdoptiumBinary,
+    release_name: String,
+}
+
+#[derive(Deserialize)]
+struct AdoptiumBinary {
+    package: AdoptiumPackage,
+}
+
+#[derive(Deserialize)]
+struct AdoptiumPackage {
+    name: String,
+    link: String,
+    checksum: String,
+    size: u64,
+}
+
+/// Written next to every unpacked JDK so an install can be traced back to the exact release.
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct JdkRelease {
+    /// Adoptium release, e.g. `jdk-21.0.5+11`.
+    pub release_name: String,
+    pub java_version: JavaVersion,
+    /// sha256 of the downloaded archive, also the name of the directory the JDK lives in.
+    pub sha256: String,
+    /// Top-level directory of the unpacked archive.
+    pub jdk_dir: PathBuf,
+}
+
+impl JdkRelease {
+    const FILE_NAME: &str = "feather-release.json";
+
+    fn read(release_dir: &Path) -> Result<Self> {
+        let path = release_dir.join(Self::FILE_NAME);
+        let contents =
+            std::fs::read(&path).with_context(|| format!("Failed to read {}", path.display()))?;
+
+        serde_json::from_slice(&contents)
+            .with_context(|| format!("Failed to parse {}", path.display()))
+    }
+
+    fn write(&self, release_dir: &Path) -> Result<()> {
+        let path = release_dir.join(Self::FILE_NAME);
+
+        std::fs::write(&path, serde_json::to_vec_pretty(self)?)
+            .with_context(|| format!("Failed to write {}", path.display()))
+    }
+}
+
+/// A Java version linked in the cache, as reported by `feather java list`.
+#[derive(Debug)]
+pub struct InstalledJava {
+    pub version: JavaVersion,
+    /// Unpacked JDK the version symlink points at.
+    pub jdk_dir: PathBuf,
+    /// Missing for JDKs unpacked before releases were recorded.
+    pub release: Option<JdkRelease>,
+    /// Size of the release directory on disk.
+    pub size: u64,
+}
+
 pub struct JavaInstaller {
     cache_dir: PathBuf,
 }
@@ -37,19 +113,28 @@ impl JavaInstaller {
         }
     }
 
-    pub fn determine_java_version(&self, mc_version: &Versioning) -> JavaVersion {
+    /// Reads the Java version Mojang lists for `mc_version`, falling back to a built-in table
+    /// when the version JSON cannot be fetched.
+    pub async fn determine_java_version(&self, mc_version: &Versioning) -> JavaVersion {
         tracing::debug!(
             "Determining required Java version for Minecraft version {}",
             mc_version
         );
 
-        match mc_version {
-            Versioning::Ideal(v) if v.major == 1 && v.minor >= 20 && v.patch >= 5 => {
-                JavaVersion::Java21
+        match fetch_java_version(mc_version).await {
+            Ok(Some(java_version)) => java_version,
+            Ok(None) => {
+                tracing::debug!("Minecraft {} does not list a Java version", mc_version);
+                fallback_java_version(mc_version)
+            }
+            Err(e) => {
+                tracing::warn!(
+                    "Failed to look up the Java version for Minecraft {}: {:#}. Guessing instead.",
+                    mc_version,
+                    e
+                );
+                fallback_java_version(mc_version)
             }
-            Versioning::Ideal(v) if v.major == 1 && v.minor >= 17 => JavaVersion::Java17,
-            Versioning::Ideal(v) if v.major == 1 && v.minor < 17 => JavaVersion::Java8,
-            _ => JavaVersion::Java17,
         }
     }
 
@@ -71,6 +156,41 @@ impl JavaInstaller {
         }
     }
 
+    /// Looks up the newest Temurin JDK for this platform, including its published sha256.
+    ///
+    /// The sha256 arrives in the same response as the download link, so it catches corrupted
+    /// or truncated archives but not a compromised API. The `.sig` files Adoptium publishes are
+    /// not checked.
+    async fn get_latest_release(&self, java_version: &JavaVersion) -> Result<AdoptiumAsset> {
+        let os = match OS {
+            "linux" => "linux",
+            "macos" => "mac",
+            _ => return Err(anyhow!("Unsupported OS: {}", OS)),
+        };
+        let architecture = match ARCH {
+            "x86_64" => "x64",
+            "aarch64" => "aarch64",
+            _ => return Err(anyhow!("Unsupported architecture: {}", ARCH)),
+        };
+
+        let url = format!(
+            "https://api.adoptium.net/v3/assets/latest/{java_version}/hotspot?architecture={architecture}&image_type=jdk&os={os}&vendor=eclipse"
+        );
+
+        tracing::debug!("Fetching Java {} release from {}", java_version, url);
+
+        let assets = network::send(|client| client.get(&url))
+            .await
+            .with_context(|| format!("Failed to fetch Java {java_version} releases"))?
+            .json::<Vec<AdoptiumAsset>>()
+            .await
+            .context("Failed to parse Adoptium assets")?;
+
+        assets.into_iter().next().with_context(|| {
+            format!("Adoptium has no Java {java_version} JDK for {os}/{architecture}")
+        })
+    }
+
     pub async fn install(&self, java_version: JavaVersion) -> Result<PathBuf> {
         let version_specific_path = self.cache_dir.join(java_version.to_string());
 
@@ -78,7 +198,14 @@ impl JavaInstaller {
             match std::fs::read_link(&version_specific_path) {
                 Ok(link) => {
                     if self.cache_dir.join(&link).exists() {
-                        tracing::info!("Java {} already installed", java_version);
+                        match self.installed_release(&java_version) {
+                            Ok(release) => tracing::info!(
+                                "Java {} ({}) already installed",
+                                java_version,
+                                release.release_name
+                            ),
+                            Err(_) => tracing::info!("Java {} already installed", java_version),
+                        }
                         return Ok(self.get_java_executable(&java_version));
                     } else {
                         tracing::warn!(
@@ -104,44 +231,35 @@ impl JavaInstaller {
             self.cache_dir.display()
         );
 
-        let os_str = match OS {
-            "linux" => "linux",
-            "macos" => "mac",
-            _ => return Err(anyhow!("Unsupported OS: {}", OS)),
-        };
-
-        let url = format!(
-            "https://api.adoptium.net/v3/binary/latest/{java_version}/ga/{os_str}/{ARCH}/jdk/hotspot/normal/eclipse"
-        );
-
-        tracing::debug!("Downloading Java from: {}", url);
-
-        let response = reqwest::get(&url)
-            .await
-            .with_context(|| format!("Failed to download Java from {url}"))?;
-
-        if !response.status().is_success() {
+        let asset = self.get_latest_release(&java_version).await?;
+        let package = asset.binary.package;
+        let sha256 = package.checksum.to_ascii_lowercase();
+        // The checksum and file name become paths in the cache, so only well-formed ones are used.
+        if sha256.len() != 64 || !sha256.bytes().all(|b| b.is_ascii_hexdigit()) {
             return Err(anyhow!(
-                "Failed to download Java: HTTP {}",
-                response.status()
+                "Adoptium returned an invalid sha256 '{}' for {}",
+                package.checksum,
+                asset.release_name
+            ));
+        }
+        if Path::new(&package.name).file_name() != Some(OsStr::new(&package.name)) {
+            return Err(anyhow!(
+                "Adoptium returned an invalid archive name '{}' for {}",
+                package.name,
+                asset.release_name
             ));
         }
 
-        let body = response
-            .bytes()
-            .await
-            .context("Failed to read Java download response")?;
-
-        let cursor = std::io::Cursor::new(&body);
-        let gz_decoder = GzDecoder::new(cursor);
-        let mut archive = Archive::new(gz_decoder);
+        // Releases are keyed by their archive checksum, so a rebuilt archive never reuses a
+        // directory unpacked from different bytes.
+        let release_dir = self.cache_dir.join(&sha256);
 
-        let mut hasher = FxHasher::default();
-        hasher.write(&body);
-        let hash = hasher.finish();
+        if let Ok(release) = JdkRelease::read(&release_dir) {
+            tracing::info!("Reusing unpacked {}", release.release_name);
 
-        let dir_name = format!("{hash:x}");
-        let jdk_install_path = self.cache_dir.join(&dir_name);
+            self.link_version(&java_version, &release_dir.join(&release.jdk_dir))?;
+            return Ok(self.get_java_executable(&java_version));
+        }
 
         std::fs::create_dir_all(&self.cache_dir).with_context(|| {
             format!(
@@ -150,14 +268,42 @@ impl JavaInstaller {
             )
         })?;
 
+        tracing::debug!("Downloading {} from: {}", asset.release_name, package.link);
+
+        let archive_path = self.cache_dir.join(&package.name);
+
+        // JDK archives are large, so they are streamed to disk and resumed after interruptions.
+        network::download_file(
+            &package.link,
+            Some(&Checksum::Sha256(sha256.clone())),
+            Some(package.size),
+            &archive_path,
+        )
+        .await
+        .with_context(|| format!("Failed to download Java from {}", package.link))?;
+
+        // Leftovers of an interrupted unpack have no release metadata and are started over.
+        if release_dir.exists() {
+            std::fs::remove_dir_all(&release_dir)
+                .with_context(|| format!("Failed to remove {}", release_dir.display()))?;
+        }
+
+        let archive_file = File::open(&archive_path)
+            .with_context(|| format!("Failed to open {}", archive_path.display()))?;
+        let gz_decoder = GzDecoder::new(BufReader::new(archive_file));
+        let mut archive = Archive::new(gz_decoder);
+
         archive
-            .unpack(&jdk_install_path)
-            .with_context(|| format!("Failed to unpack Java to {jdk_install_path:?}"))?;
+            .unpack(&release_dir)
+            .with_context(|| format!("Failed to unpack Java to {release_dir:?}"))?;
+
+        std::fs::remove_file(&archive_path)
+            .with_context(|| format!("Failed to remove {}", archive_path.display()))?;
 
-        let mut entries = std::fs::read_dir(&jdk_install_path).with_context(|| {
+        let mut entries = std::fs::read_dir(&release_dir).with_context(|| {
             format!(
                 "Failed to read unpacked JDK directory: {}",
-                jdk_install_path.display()
+                release_dir.display()
             )
         })?;
 
@@ -166,11 +312,35 @@ impl JavaInstaller {
             _ => {
                 return Err(anyhow!(
                     "Could not find JDK directory inside unpacked archive at {}",
-                    jdk_install_path.display()
+                    release_dir.display()
                 ));
             }
         };
 
+        JdkRelease {
+            release_name: asset.release_name.clone(),
+            java_version,
+            sha256,
+            jdk_dir: jdk_subdirectory_path
+                .strip_prefix(&release_dir)?
+                .to_path_buf(),
+        }
+        .write(&release_dir)?;
+
+        self.link_version(&java_version, &jdk_subdirectory_path)?;
+
+        tracing::info!(
+            "Java {} ({}) installed successfully",
+            java_version,
+            asset.release_name
+        );
+        Ok(self.get_java_executable(&java_version))
+    }
+
+    /// Points `<cache>/<version>` at an unpacked JDK and returns its `java` executable.
+    pub fn link_version(&self, java_version: &JavaVersion, jdk_dir: &Path) -> Result<PathBuf> {
+        let version_specific_path = self.cache_dir.join(java_version.to_string());
+
         if version_specific_path.exists() || version_specific_path.is_symlink() {
             std::fs::remove_file(&version_specific_path).with_context(|| {
                 format!(
@@ -180,17 +350,321 @@ impl JavaInstaller {
             })?;
         }
 
-        std::os::unix::fs::symlink(&jdk_subdirectory_path, &version_specific_path).with_context(
-            || {
-                format!(
-                    "Failed to create symlink from {} to {}",
-                    version_specific_path.display(),
-                    jdk_subdirectory_path.display()
-                )
-            },
-        )?;
+        std::os::unix::fs::symlink(jdk_dir, &version_specific_path).with_context(|| {
+            format!(
+                "Failed to create symlink from {} to {}",
+                version_specific_path.display(),
+                jdk_dir.display()
+            )
+        })?;
 
-        tracing::info!("Java {} installed successfully", java_version);
-        Ok(self.get_java_executable(&java_version))
+        Ok(self.get_java_executable(java_version))
+    }
+
+    /// The release `<cache>/<version>` currently points at.
+    pub fn installed_release(&self, java_version: &JavaVersion) -> Result<JdkRelease> {
+        let jdk_dir = self.installed_jdk_dir(java_version)?;
+        let release_dir = jdk_dir
+            .parent()
+            .with_context(|| format!("{} has no parent directory", jdk_dir.display()))?;
+
+        JdkRelease::read(release_dir)
+    }
+
+    /// The unpacked JDK directory `<cache>/<version>` currently points at.
+    pub fn installed_jdk_dir(&self, java_version: &JavaVersion) -> Result<PathBuf> {
+        let version_specific_path = self.cache_dir.join(java_version.to_string());
+
+        std::fs::read_link(&version_specific_path)
+            .map(|link| self.cache_dir.join(link))
+            .with_context(|| format!("Java {java_version} is not installed"))
+    }
+
+    /// Every Java version linked in the cache.
+    pub fn installed(&self) -> Result<Vec<InstalledJava>> {
+        if !self.cache_dir.exists() {
+            return Ok(Vec::new());
+        }
+
+        let mut versions = std::fs::read_dir(&self.cache_dir)
+            .with_context(|| format!("Failed to read {}", self.cache_dir.display()))?
+            .filter_map(|entry| entry.ok())
+            .filter_map(|entry| entry.file_name().to_str()?.parse::<JavaVersion>().ok())
+            .collect::<Vec<_>>();
+        versions.sort();
+
+        let mut installed = Vec::new();
+
+        for version in versions {
+            let Ok(jdk_dir) = self.installed_jdk_dir(&version) else {
+                continue;
+            };
+            let release_dir = jdk_dir.parent().unwrap_or(&jdk_dir);
+
+            installed.push(InstalledJava {
+                release: JdkRelease::read(release_dir).ok(),
+                size: dir_size(release_dir)?,
+                version,
+                jdk_dir,
+            });
+        }
+
+        Ok(installed)
+    }
+
+    /// Removes unpacked JDKs that no version symlink points at and no executable in `in_use`
+    /// lives in, returning the removed directories and their sizes.
+    pub fn prune(&self, in_use: &[PathBuf]) -> Result<Vec<(PathBuf, u64)>> {
+        if !self.cache_dir.exists() {
+            return Ok(Vec::new());
+        }
+
+        let linked = self
+            .installed()?
+            .into_iter()
+            .filter_map(|java| java.jdk_dir.parent().map(Path::to_path_buf))
+            .collect::<Vec<_>>();
+        // Instances record executables through version symlinks such as `21/bin/java`, which
+        // only reveal the JDK they use once resolved.
+        let in_use = in_use
+            .iter()
+            .filter_map(|executable| std::fs::canonicalize(executable).ok())
+            .collect::<Vec<_>>();
+
+        let mut removed = Vec::new();
+
+        for entry in std::fs::read_dir(&self.cache_dir)
+            .with_context(|| format!("Failed to read {}", self.cache_dir.display()))?
+        {
+            let entry = entry?;
+            let path = entry.path();
+
+            // Version symlinks and archives of downloads in progress are left alone.
+            if !entry.file_type()?.is_dir()
+                || linked.contains(&path)
+                || in_use.iter().any(|executable| {
+                    std::fs::canonicalize(&path).is_ok_and(|path| executable.starts_with(path))
+                })
+            {
+                continue;
+            }
+
+            let size = dir_size(&path)?;
+            std::fs::remove_dir_all(&path)
+                .with_context(|| format!("Failed to remove {}", path.display()))?;
+            removed.push((path, size));
+        }
+
+        Ok(removed)
+    }
+}
+
+async fn fetch_java_version(mc_version: &Versioning) -> Result<Option<JavaVersion>> {
+    let versions = MinecraftVersions::new().await?;
+    let version = versions
+        .get_version(mc_version)
+        .with_context(|| format!("Minecraft version {mc_version} not found"))?;
+
+    Ok(versions
+        .get_details(version)
+        .await?
+        .java_version
+        .map(|java_version| JavaVersion(java_version.major_version)))
+}
+
+/// Java versions Mojang listed when this table was written, used when offline.
+fn fallback_java_version(mc_version: &Versioning) -> JavaVersion {
+    // Short versions such as "1.12" parse as general versions, so compare components.
+    let minor = match (mc_version.nth(0), mc_version.nth(1)) {
+        (Some(1), Some(minor)) => minor,
+        // Year-based versions such as "26.1" and their snapshots come after every 1.x release,
+        // so they get the newest Java in the table.
+        (Some(_), _) => return JavaVersion(25),
+        // Weekly snapshots such as "24w14a" ended with the 1.21 releases.
+        _ => return JavaVersion(21),
+    };
+    let patch = mc_version.nth(2).unwrap_or(0);
+
+    match minor {
+        _ if minor > 20 || (minor == 20 && patch >= 5) => JavaVersion(21),
+        18.. => JavaVersion(17),
+        17 => JavaVersion(16),
+        _ => JavaVersion(8),
+    }
+}
+
+/// Finds `java` in `JAVA_HOME`, falling back to `PATH`.
+pub fn find_system_java() -> Result<PathBuf> {
+    if let Some(java_home) = std::env::var_os("JAVA_HOME")
+        && let Ok(executable) = java_executable_at(Path::new(&java_home))
+    {
+        return Ok(executable);
+    }
+
+    std::env::var_os("PATH")
+        .iter()
+        .flat_map(std::env::split_paths)
+        .map(|dir| dir.join("java"))
+        .find(|executable| executable.is_file())
+        .context("No Java found in JAVA_HOME or PATH")
+}
+
+/// Accepts either a `java` executable or a Java home directory containing `bin/java`.
+pub fn java_executable_at(path: &Path) -> Result<PathBuf> {
+    let executable = if path.is_dir() {
+        path.join("bin").join("java")
+    } else {
+        path.to_path_buf()
+    };
+
+    if !executable.is_file() {
+        return Err(anyhow!("No Java executable at {}", executable.display()));
+    }
+
+    Ok(executable)
+}
+
+/// Runs `java -version` and returns the feature release, e.g. 8 for `1.8.0_392` and 21 for
+/// `21.0.5`.
+pub fn probe_java_version(executable: &Path) -> Result<u32> {
+    let output = std::process::Command::new(executable)
+        .arg("-version")
+        .output()
+        .with_context(|| format!("Failed to run {} -version", executable.display()))?;
+
+    if !output.status.success() {
+        return Err(anyhow!("{} -version failed", executable.display()));
+    }
+
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    parse_java_version(&stderr).with_context(|| {
+        format!(
+            "Unrecognised {} -version output: {}",
+            executable.display(),
+            stderr.trim()
+        )
+    })
+}
+
+/// Major version from `java -version` output, e.g. `openjdk version "21.0.5" 2024-10-15` or
+/// `java version "1.8.0_392"` before Java 9 dropped the leading `1.`.
+fn parse_java_version(output: &str) -> Option<u32> {
+    // Lines such as `Picked up JAVA_TOOL_OPTIONS: -Dfoo="bar"` may come first.
+    let version = output
+        .lines()
+        .find_map(|line| line.split_once(" version \"")?.1.split('"').next())?;
+
+    let mut components = version.split(['.', '_', '-', '+']);
+    let major = match components.next() {
+        Some("1") => components.next(),
+        first => first,
+    };
+
+    major?.parse().ok()
+}
+
+fn dir_size(dir: &Path) -> Result<u64> {
+    let mut size = 0;
+    let mut pending = vec![dir.to_path_buf()];
+
+    while let Some(current) = pending.pop() {
+        for entry in std::fs::read_dir(&current)
+            .with_context(|| format!("Failed to read {}", current.display()))?
+        {
+            let entry = entry?;
+            let metadata = entry.metadata()?;
+
+            if metadata.is_dir() {
+                pending.push(entry.path());
+            } else {
+                size += metadata.len();
+            }
+        }
+    }
+
+    Ok(size)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_java_8() {
+        let output = "openjdk version \"1.8.0_392\"\n\
+            OpenJDK Runtime Environment (Temurin)(build 1.8.0_392-b08)\n";
+        assert_eq!(parse_java_version(output), Some(8));
+    }
+
+    #[test]
+    fn parses_modern_releases() {
+        let output = "openjdk version \"21.0.5\" 2024-10-15 LTS\n\
+            OpenJDK Runtime Environment Temurin-21.0.5+11 (build 21.0.5+11-LTS)\n";
+        assert_eq!(parse_java_version(output), Some(21));
+        assert_eq!(
+            parse_java_version("openjdk version \"25\" 2025-09-16\n"),
+            Some(25)
+        );
+        assert_eq!(parse_java_version("java version \"17.0.9+9\"\n"), Some(17));
+    }
+
+    #[test]
+    fn parses_early_access_builds() {
+        assert_eq!(
+            parse_java_version("openjdk version \"21-ea\" 2023-09-19\n"),
+            Some(21)
+        );
+    }
+
+    #[test]
+    fn skips_tool_options_banner() {
+        let output = "Picked up JAVA_TOOL_OPTIONS: -Dfile.encoding=\"UTF-8\"\n\
+            openjdk version \"17.0.9\" 2023-10-17\n";
+        assert_eq!(parse_java_version(output), Some(17));
+    }
+
+    #[test]
+    fn rejects_unrecognised_output() {
+        assert_eq!(parse_java_version(""), None);
+        assert_eq!(
+            parse_java_version("Error: could not find libjava.so\n"),
+            None
+        );
+        assert_eq!(parse_java_version("openjdk version \"abc\"\n"), None);
+    }
+
+    fn fallback(version: &str) -> u32 {
+        fallback_java_version(&Versioning::new(version).unwrap()).0
+    }
+
+    #[test]
+    fn fallback_follows_mojang_table() {
+        assert_eq!(fallback("1.21.1"), 21);
+        assert_eq!(fallback("1.21.0"), 21);
+        assert_eq!(fallback("1.20.5"), 21);
+        assert_eq!(fallback("1.20.4"), 17);
+        assert_eq!(fallback("1.18.2"), 17);
+        assert_eq!(fallback("1.17.1"), 16);
+        assert_eq!(fallback("1.17"), 16);
+        assert_eq!(fallback("1.16.5"), 8);
+    }
+
+    #[test]
+    fn fallback_handles_short_versions() {
+        assert_eq!(fallback("1.12"), 8);
+        assert_eq!(fallback("1.21"), 21);
+    }
+
+    #[test]
+    fn fallback_handles_snapshots() {
+        assert_eq!(fallback("24w14a"), 21);
+        assert_eq!(fallback("1.21-pre1"), 21);
+    }
+
+    #[test]
+    fn fallback_gives_year_versions_newest_java() {
+        assert_eq!(fallback("26.1"), 25);
+        assert_eq!(fallback("26.1.2"), 25);
+        assert_eq!(fallback("26.1-snapshot-1"), 25);
     }
 }
@@ -0,0 +1,159 @@
+use anyhow::{Context, Result};
+use indicatif::{HumanBytes, HumanDuration};
+use std::{
+    fmt::Write,
+    time::{Duration, Instant},
+};
+
+use crate::{
+    cli::printer::Printer, config::ConfigGenerator, instance::Instance, rcon::RconClient, systemd,
+};
+
+/// Stops an instance the way an operator would: `stop` over RCON lets the server save every
+/// world and exit on its own. Servers that cannot be reached or do not exit in time get a
+/// regular `systemctl stop`, which sends SIGTERM.
+pub async fn stop(instance: &Instance, timeout: Duration) -> Result<()> {
+    let unit_name = systemd::unit_name(&instance.name);
+
+    let Some(pid) = systemd::main_pid(&unit_name)? else {
+        tracing::info!("{} is not running", unit_name);
+        return Ok(());
+    };
+
+    match graceful_stop(instance).await {
+        Ok(()) => {
+            tracing::info!("Waiting for {} to save its worlds...", instance.name);
+
+            if wait_for_exit(&unit_name, pid, timeout).await? {
+                tracing::info!("{} saved its worlds and stopped", instance.name);
+            } else {
+                tracing::warn!(
+                    "{} did not stop within {}, sending SIGTERM",
+                    instance.name,
+                    HumanDuration(timeout)
+                );
+            }
+        }
+        Err(e) => {
+            tracing::warn!(
+                "Failed to stop {} from inside the game: {:#}. Sending SIGTERM instead.",
+                instance.name,
+                e
+            );
+        }
+    }
+
+    // Marks the unit stopped after a clean exit, and terminates a server that is still running.
+    systemd::systemctl("stop", &unit_name)
+}
+
+async fn graceful_stop(instance: &Instance) -> Result<()> {
+    let (port, password) = ConfigGenerator::new(&instance.dir)
+        .read_rcon()?
+        .context("RCON is not enabled in server.properties")?;
+
+    let mut rcon = RconClient::connect(port, &password).await?;
+    // The server may close the connection before answering, which is fine once it got the command.
+    if let Err(e) = rcon.command("stop").await {
+        tracing::debug!("RCON connection closed after stop: {:#}", e);
+    }
+
+    Ok(())
+}
+
+/// Waits until the unit no longer runs `pid`, returning whether it exited in time.
+async fn wait_for_exit(unit_name: &str, pid: u32, timeout: Duration) -> Result<bool> {
+    let started = Instant::now();
+
+    while started.elapsed() < timeout {
+        if systemd::main_pid(unit_name)? != Some(pid) {
+            return Ok(true);
+        }
+
+        tokio::time::sleep(Duration::from_millis(500)).await;
+    }
+
+    Ok(false)
+}
+
+pub fn print_status(instance: &Instance, lines: usize, printer: Printer) -> Result<()> {
+    let unit_name = systemd::unit_name(&instance.name);
+    let properties = systemd::show(
+        &unit_name,
+        &[
+            "ActiveState",
+            "SubState",
+            "MainPID",
+            "ExecMainStartTimestampMonotonic",
+            "MemoryCurrent",
+        ],
+    )?;
+    let property = |key: &str| properties.get(key).map(String::as_str).unwrap_or_default();
+
+    let mut stdout = printer.stdout();
+    stdout.write_str(&format!(
+        "{}: {} ({})",
+        unit_name,
+        property("ActiveState"),
+        property("SubState")
+    ))?;
+
+    if let Some(pid) = property("MainPID")
+        .parse::<u32>()
+        .ok()
+        .filter(|pid| *pid != 0)
+    {
+        stdout.write_str(&format!("PID: {pid}"))?;
+
+        if let Some(uptime) = uptime(property("ExecMainStartTimestampMonotonic")) {
+            stdout.write_str(&format!("Uptime: {}", HumanDuration(uptime)))?;
+        }
+
+        // Unset while the unit has no memory accounting.
+        if let Ok(memory) = property("MemoryCurrent").parse::<u64>() {
+            stdout.write_str(&format!("Memory: {}", HumanBytes(memory)))?;
+        }
+
+        if let Ok(cmdline) = std::fs::read(format!("/proc/{pid}/cmdline")) {
+            let command = cmdline
+                .split(|byte| *byte == 0)
+                .filter(|arg| !arg.is_empty())
+                .map(String::from_utf8_lossy)
+                .collect::<Vec<_>>()
+                .join(" ");
+            stdout.write_str(&format!("Command: {command}"))?;
+        }
+    }
+
+    stdout.write_str(&format!("Port: {}", instance.port))?;
+    stdout.write_str(&format!("Directory: {}", instance.dir.display()))?;
+
+    let journal = systemd::journal(&unit_name, lines)?;
+    if !journal.trim().is_empty() {
+        stdout.write_str("")?;
+        for line in journal.lines() {
+            stdout.write_str(line)?;
+        }
+    }
+
+    Ok(())
+}
+
+/// Time since the main process started, from systemd's monotonic timestamp in microseconds.
+fn uptime(started_monotonic: &str) -> Option<Duration> {
+    let started = Duration::from_micros(
+        started_monotonic
+            .parse::<u64>()
+            .ok()
+            .filter(|us| *us != 0)?,
+    );
+    let now = std::fs::read_to_string("/proc/uptime")
+        .ok()?
+        .split_whitespace()
+        .next()?
+        .parse::<f64>()
+        .ok()
+        .map(Duration::from_secs_f64)?;
+
+    now.checked_sub(started)
+}
@@ -1,3 +1,4 @@
+use crate::cli::printer::Printer;
 use tracing_subscriber::{
     EnvFilter,
     fmt::{format::FmtSpan, time::ChronoLocal},
@@ -5,15 +6,22 @@ use tracing_subscriber::{
     util::SubscriberInitExt,
 };
 
-pub fn init() -> Result<(), Box<dyn std::error::Error>> {
-    let filter = EnvFilter::new("info")
+pub fn init(printer: Printer) -> Result<(), Box<dyn std::error::Error>> {
+    let level = match printer {
+        Printer::Silent => "error",
+        Printer::Quiet => "warn",
+        Printer::Default => "info",
+        Printer::Verbose => "debug",
+    };
+
+    let filter = EnvFilter::new(level)
         .add_directive("reqwest=warn".parse()?)
         .add_directive("hyper=warn".parse()?)
         .add_directive("rustls=warn".parse()?)
         .add_directive("h2=warn".parse()?)
         .add_directive("tokio=warn".parse()?)
         .add_directive("tracing=warn".parse()?)
-        .add_directive("feather=info".parse()?);
+        .add_directive(format!("feather={level}").parse()?);
 
     tracing_subscriber::registry()
         .with(filter)
@@ -1,26 +1,49 @@
+mod bundle;
+mod cache;
+mod checksum;
 mod cli;
 mod config;
 mod installer;
+mod instance;
 mod java;
+mod lifecycle;
 mod logging;
 mod minecraft;
 mod modpack;
+mod network;
+mod proxy;
+mod rcon;
+mod supervisor;
+mod systemd;
 
-use std::{path::PathBuf, sync::LazyLock};
+use std::{fmt::Write, path::PathBuf, sync::LazyLock, time::Duration};
 
-use anyhow::Result;
-use cli::Commands;
+use anyhow::{Result, anyhow};
+use cache::Cache;
+use cli::{BundleCommand, CacheCommand, Commands, InitArgs, JavaCommand, printer::Printer};
+use config::ConfigGenerator;
+use indicatif::HumanBytes;
 use installer::MinecraftServerInstaller;
+use instance::{Instance, Registry};
+use java::JavaInstaller;
 use modpack::MinecraftProfile;
+use network::Downloader;
+use proxy::ProxyInstaller;
+use supervisor::Supervisor;
 
 static JAVA_CACHE_DIR: LazyLock<PathBuf> = LazyLock::new(|| PathBuf::from("/opt/feather/java"));
 static HOME_DIR: LazyLock<PathBuf> = LazyLock::new(|| PathBuf::from("/opt/feather"));
 static MINECRAFT_SERVERS_DIR: LazyLock<PathBuf> = LazyLock::new(|| HOME_DIR.join("servers"));
+static PROXIES_DIR: LazyLock<PathBuf> = LazyLock::new(|| HOME_DIR.join("proxies"));
+static CACHE_DIR: LazyLock<PathBuf> = LazyLock::new(|| HOME_DIR.join("cache"));
+static TEMPLATES_DIR: LazyLock<PathBuf> = LazyLock::new(|| HOME_DIR.join("templates"));
+static INSTANCES_FILE: LazyLock<PathBuf> = LazyLock::new(|| HOME_DIR.join("instances.json"));
 
 fn main() -> Result<()> {
-    logging::init().unwrap();
-
     let cli = cli::parse();
+    let printer = cli.printer();
+
+    logging::init(printer).unwrap();
 
     match cli.command {
         Commands::Init(args) => {
@@ -30,33 +53,419 @@ fn main() -> Result<()> {
                 .unwrap();
 
             runtime.block_on(async {
-                let profile = MinecraftProfile::try_import(&args.file)?;
+                if let Some(bundle) = &args.from_bundle {
+                    std::fs::create_dir_all(&*HOME_DIR)?;
+                    // Staged next to the install so its contents can be moved rather than copied.
+                    let staging = tempfile::Builder::new()
+                        .prefix("bundle-")
+                        .tempdir_in(&*HOME_DIR)?;
+                    let lock = bundle::extract(bundle, staging.path())?;
+                    let snapshot = lock.profile.snapshot();
+
+                    let installer = MinecraftServerInstaller::new(
+                        lock.profile.clone(),
+                        &args,
+                        &downloader(&args, printer),
+                        &JAVA_CACHE_DIR,
+                        &MINECRAFT_SERVERS_DIR,
+                        &PROXIES_DIR,
+                        &TEMPLATES_DIR,
+                    );
+
+                    Registry::load(&INSTANCES_FILE)?.check_new(
+                        installer.name(),
+                        args.port,
+                        args.force,
+                    )?;
+
+                    let instance = installer.install_bundle(staging.path(), &lock)?;
+                    register_instance(instance)?;
+
+                    tracing::info!(
+                        "Feather server initialization for '{}' from {} finished successfully.",
+                        snapshot,
+                        bundle.display()
+                    );
+
+                    return Ok(());
+                }
+
+                let profile = resolve_profile(&args).await?;
+                let snapshot = profile.snapshot();
 
                 let installer = MinecraftServerInstaller::new(
                     profile,
                     &args,
+                    &downloader(&args, printer),
                     &JAVA_CACHE_DIR,
                     &MINECRAFT_SERVERS_DIR,
+                    &PROXIES_DIR,
+                    &TEMPLATES_DIR,
                 );
 
-                installer.install().await?;
+                Registry::load(&INSTANCES_FILE)?.check_new(
+                    installer.name(),
+                    args.port,
+                    args.force,
+                )?;
+
+                let instance = installer.install().await?;
+                let name = instance.name.clone();
+                register_instance(instance)?;
 
                 tracing::info!(
-                    "Feather server initialization for modpack '{}' finished successfully.",
-                    args.file
+                    "Feather server initialization for '{}' finished successfully.",
+                    snapshot
                 );
                 tracing::info!(
                     "Minecraft server instance is being set up. Check logs for specific server directory."
                 );
                 tracing::info!(
-                    "{}\n{}",
-                    "After completion, you might need to run the downloaded installer jar (if Fabric/Forge/etc.) ",
-                    "inside the server's directory, then use systemd commands (if configured) to manage the server."
+                    "Start the server with `feather start {}`, check on it with `feather status {}`.",
+                    name,
+                    name
+                );
+
+                Ok::<(), anyhow::Error>(())
+            })?;
+        }
+        Commands::Proxy(args) => {
+            let runtime = tokio::runtime::Builder::new_current_thread()
+                .enable_all()
+                .build()
+                .unwrap();
+
+            runtime.block_on(async {
+                let installer = ProxyInstaller::new(
+                    &args,
+                    &JAVA_CACHE_DIR,
+                    &PROXIES_DIR,
+                    &MINECRAFT_SERVERS_DIR,
+                    &INSTANCES_FILE,
+                );
+
+                installer.install().await?;
+
+                tracing::info!(
+                    "Feather {} proxy initialization finished successfully.",
+                    args.kind
+                );
+                tracing::info!(
+                    "Restart the backend servers so they pick up the forwarding configuration."
                 );
 
                 Ok::<(), anyhow::Error>(())
             })?;
         }
+        Commands::Cache(args) => {
+            run_cache_command(&Cache::new(&CACHE_DIR), &args.command, printer)?;
+        }
+        Commands::Bundle(args) => match args.command {
+            BundleCommand::Create(args) => {
+                if args.init.from_bundle.is_some() {
+                    return Err(anyhow!("--from-bundle cannot be used to create a bundle"));
+                }
+
+                let runtime = tokio::runtime::Builder::new_current_thread()
+                    .enable_all()
+                    .build()
+                    .unwrap();
+
+                runtime.block_on(async {
+                    let profile = resolve_profile(&args.init).await?;
+                    let staging = tempfile::tempdir()?;
+
+                    let installer = MinecraftServerInstaller::new(
+                        profile,
+                        &args.init,
+                        &downloader(&args.init, printer),
+                        &staging.path().join(bundle::JAVA_DIR),
+                        &staging.path().join("servers"),
+                        &staging.path().join("proxies"),
+                        &TEMPLATES_DIR,
+                    );
+
+                    installer.create_bundle(&args.output).await?;
+
+                    tracing::info!(
+                        "Bundle written to {}. Install it with `feather init --from-bundle {}`.",
+                        args.output.display(),
+                        args.output.display()
+                    );
+
+                    Ok::<(), anyhow::Error>(())
+                })?;
+            }
+        },
+        Commands::Java(args) => {
+            let runtime = tokio::runtime::Builder::new_current_thread()
+                .enable_all()
+                .build()
+                .unwrap();
+
+            runtime.block_on(run_java_command(
+                &JavaInstaller::new(&JAVA_CACHE_DIR),
+                &args.command,
+                printer,
+            ))?;
+        }
+        Commands::Start(args) => {
+            let registry = Registry::load(&INSTANCES_FILE)?;
+            let instance = registry.get(&args.name)?;
+
+            systemd::systemctl("start", &systemd::unit_name(&instance.name))?;
+        }
+        Commands::Stop(args) => {
+            let registry = Registry::load(&INSTANCES_FILE)?;
+            let instance = registry.get(&args.name)?;
+
+            let runtime = tokio::runtime::Builder::new_current_thread()
+                .enable_all()
+                .build()
+                .unwrap();
+
+            runtime.block_on(lifecycle::stop(instance, Duration::from_secs(args.timeout)))?;
+        }
+        Commands::Restart(args) => {
+            let registry = Registry::load(&INSTANCES_FILE)?;
+            let instance = registry.get(&args.name)?;
+
+            let runtime = tokio::runtime::Builder::new_current_thread()
+                .enable_all()
+                .build()
+                .unwrap();
+
+            runtime.block_on(lifecycle::stop(instance, Duration::from_secs(args.timeout)))?;
+            systemd::systemctl("start", &systemd::unit_name(&instance.name))?;
+        }
+        Commands::Status(args) => {
+            let registry = Registry::load(&INSTANCES_FILE)?;
+            let instance = registry.get(&args.name)?;
+
+            lifecycle::print_status(instance, args.lines, printer)?;
+        }
+        Commands::Enable(args) => {
+            let registry = Registry::load(&INSTANCES_FILE)?;
+            let instance = registry.get(&args.name)?;
+
+            systemd::systemctl("enable", &systemd::unit_name(&instance.name))?;
+        }
+        Commands::Disable(args) => {
+            let registry = Registry::load(&INSTANCES_FILE)?;
+            let instance = registry.get(&args.name)?;
+
+            systemd::systemctl("disable", &systemd::unit_name(&instance.name))?;
+        }
+        Commands::Run(args) => {
+            let registry = Registry::load(&INSTANCES_FILE)?;
+            let instance = registry.get(&args.name)?;
+
+            let runtime = tokio::runtime::Builder::new_current_thread()
+                .enable_all()
+                .build()
+                .unwrap();
+
+            runtime.block_on(
+                Supervisor::new(instance, args.restart, Duration::from_secs(args.timeout)).run(),
+            )?;
+        }
+        Commands::List => {
+            let registry = Registry::load(&INSTANCES_FILE)?;
+            let mut stdout = printer.stdout();
+
+            for instance in registry.instances() {
+                stdout.write_str(&format!(
+                    "{}  {}  port {}  {}  {}",
+                    instance.name,
+                    instance.profile.snapshot(),
+                    instance.port,
+                    instance.java.display(),
+                    instance.dir.display()
+                ))?;
+            }
+        }
+    }
+
+    Ok(())
+}
+
+/// Downloader for `feather init` and `feather bundle`, backed by the shared cache.
+fn downloader(args: &InitArgs, printer: Printer) -> Downloader {
+    Downloader::new(args.concurrent_downloads, printer, Cache::new(&CACHE_DIR))
+}
+
+fn register_instance(instance: Instance) -> Result<()> {
+    let mut registry = Registry::load(&INSTANCES_FILE)?;
+
+    tracing::info!(
+        "Registered instance '{}' on port {} in {}",
+        instance.name,
+        instance.port,
+        instance.dir.display()
+    );
+
+    registry.insert(instance);
+    registry.save(&INSTANCES_FILE)
+}
+
+async fn resolve_profile(args: &InitArgs) -> Result<MinecraftProfile> {
+    match (&args.file, &args.minecraft_version) {
+        (Some(file), _) => MinecraftProfile::try_import(file).await,
+        (None, Some(version)) => {
+            MinecraftProfile::resolve(version, args.loader.as_ref(), &args.loader_version).await
+        }
+        (None, None) => Err(anyhow!("Either FILE or --version is required")),
+    }
+}
+
+fn run_cache_command(cache: &Cache, command: &CacheCommand, printer: Printer) -> Result<()> {
+    let mut stdout = printer.stdout();
+
+    match command {
+        CacheCommand::List => {
+            let mut entries = cache.entries()?;
+            entries.sort_by(|a, b| a.path.cmp(&b.path));
+
+            for entry in entries {
+                stdout.write_str(&format!(
+                    "{}  {}  {} references",
+                    entry.checksum,
+                    HumanBytes(entry.size),
+                    entry.references
+                ))?;
+            }
+        }
+        CacheCommand::Size => {
+            let entries = cache.entries()?;
+            let total = entries.iter().map(|entry| entry.size).sum::<u64>();
+
+            stdout.write_str(&format!(
+                "{} files, {} in {}",
+                entries.len(),
+                HumanBytes(total),
+                CACHE_DIR.display()
+            ))?;
+        }
+        CacheCommand::Gc => {
+            let removed = cache.gc()?;
+            let freed = removed.iter().map(|entry| entry.size).sum::<u64>();
+
+            stdout.write_str(&format!(
+                "Removed {} unreferenced files, freed {}",
+                removed.len(),
+                HumanBytes(freed)
+            ))?;
+        }
+        CacheCommand::Verify => {
+            let corrupted = cache.verify()?;
+
+            if !corrupted.is_empty() {
+                let mut stderr = printer.stderr();
+                for (entry, error) in &corrupted {
+                    stderr.write_str(&format!("{}: {:#}", entry.path.display(), error))?;
+                }
+
+                return Err(anyhow!(
+                    "{} cached files are corrupted, remove them and reinstall the affected servers",
+                    corrupted.len()
+                ));
+            }
+
+            stdout.write_str("All cached files match their checksums")?;
+        }
+    }
+
+    Ok(())
+}
+
+async fn run_java_command(
+    java_installer: &JavaInstaller,
+    command: &JavaCommand,
+    printer: Printer,
+) -> Result<()> {
+    let mut stdout = printer.stdout();
+
+    match command {
+        JavaCommand::List => {
+            for java in java_installer.installed()? {
+                let release = java
+                    .release
+                    .map(|release| release.release_name)
+                    .unwrap_or_else(|| "unknown release".to_string());
+
+                stdout.write_str(&format!(
+                    "{}  {}  {}  {}",
+                    java.version,
+                    release,
+                    java.jdk_dir.display(),
+                    HumanBytes(java.size)
+                ))?;
+            }
+        }
+        JavaCommand::Install { version } => {
+            let java_executable = java_installer.install(*version).await?;
+
+            stdout.write_str(&format!(
+                "Java {} installed at {}",
+                version,
+                java_executable.display()
+            ))?;
+        }
+        JavaCommand::Prune => {
+            let in_use = [&*MINECRAFT_SERVERS_DIR, &*PROXIES_DIR]
+                .into_iter()
+                .filter(|dir| dir.exists())
+                .map(std::fs::read_dir)
+                .collect::<Result<Vec<_>, _>>()?
+                .into_iter()
+                .flatten()
+                .filter_map(|entry| entry.ok())
+                .map(|entry| ConfigGenerator::new(&entry.path()).read_java_executable())
+                .collect::<Result<Vec<_>>>()?
+                .into_iter()
+                .flatten()
+                .collect::<Vec<_>>();
+
+            let removed = java_installer.prune(&in_use)?;
+            let freed = removed.iter().map(|(_, size)| size).sum::<u64>();
+
+            stdout.write_str(&format!(
+                "Removed {} unused JDKs, freed {}",
+                removed.len(),
+                HumanBytes(freed)
+            ))?;
+        }
+        JavaCommand::Use { server, version } => {
+            let server_dir = MINECRAFT_SERVERS_DIR.join(server);
+            if !server_dir.join("feather.env").exists() {
+                return Err(anyhow!(
+                    "{} is not a Feather server directory",
+                    server_dir.display()
+                ));
+            }
+
+            let java_executable = java_installer.install(*version).await?;
+            ConfigGenerator::new(&server_dir).set_java_executable(&java_executable)?;
+
+            let mut registry = Registry::load(&INSTANCES_FILE)?;
+            let name = server_dir
+                .file_name()
+                .and_then(|name| name.to_str())
+                .unwrap_or_default();
+
+            if let Some(instance) = registry.get_mut(name)
+                && instance.dir == server_dir
+            {
+                instance.java = java_executable;
+                registry.save(&INSTANCES_FILE)?;
+            }
+
+            stdout.write_str(&format!(
+                "{} now runs on Java {}, restart it to apply",
+                server_dir.display(),
+                version
+            ))?;
+        }
     }
 
     Ok(())
@@ -1,40 +1,222 @@
-use crate::modpack::{LoaderType, MinecraftProfile};
-use anyhow::{Context, Result};
-use std::path::Path;
+use crate::{
+    checksum::Checksum,
+    cli::OptionalFiles,
+    modpack::{
+        CurseForgeClient, CurseForgeModpack, EnvironmentSupport, LoaderType, MinecraftProfile,
+        ModrinthFile, PackwizDownload, PackwizModpack, PackwizSide, extract_overrides,
+        safe_relative_path,
+    },
+    network::{self, Download, Downloader, MinecraftVersions, VersionType},
+};
+use anyhow::{Context, Result, anyhow};
+use feather_forge::{ForgeClient, ServerLayout};
+use feather_neoforge::NeoForgeClient;
+use feather_paper::{PaperClient, Project};
+use feather_purpur::{BuildResult, PurpurClient};
+use feather_quilt::QuiltClient;
+use serde::{Deserialize, Serialize};
+use std::{
+    fmt,
+    io::IsTerminal,
+    path::{Path, PathBuf},
+};
+
+/// How the JVM is pointed at an installed server, relative to the server directory.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub enum ServerLaunch {
+    /// A self-contained jar started with `-jar`.
+    Jar(PathBuf),
+    /// An installer-generated argument file such as NeoForge's `unix_args.txt`.
+    ArgsFile(PathBuf),
+}
+
+impl fmt::Display for ServerLaunch {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ServerLaunch::Jar(path) => write!(f, "-jar {}", path.display()),
+            ServerLaunch::ArgsFile(path) => write!(f, "@{}", path.display()),
+        }
+    }
+}
 
 pub struct MinecraftInstaller {
-    server_dir: std::path::PathBuf,
+    server_dir: PathBuf,
+    java_executable: PathBuf,
+    curseforge: CurseForgeClient,
+    optional_files: OptionalFiles,
+    downloader: Downloader,
 }
 
 impl MinecraftInstaller {
-    pub fn new(server_dir: &Path) -> Self {
+    pub fn new(
+        server_dir: &Path,
+        java_executable: &Path,
+        curseforge: &CurseForgeClient,
+        optional_files: OptionalFiles,
+        downloader: &Downloader,
+    ) -> Self {
         Self {
             server_dir: server_dir.to_path_buf(),
+            java_executable: java_executable.to_path_buf(),
+            curseforge: curseforge.clone(),
+            optional_files,
+            downloader: downloader.clone(),
         }
     }
 
-    pub async fn install(&self, profile: &MinecraftProfile) -> Result<()> {
-        match &profile.loader {
+    /// Installs the server and returns how it should be launched.
+    pub async fn install(&self, profile: &MinecraftProfile) -> Result<ServerLaunch> {
+        let launch = match &profile.loader {
             Some(loader) => match &loader.name {
                 LoaderType::Fabric => {
                     self.install_fabric_loader(profile).await?;
+                    ServerLaunch::Jar("server.jar".into())
                 }
+                LoaderType::Forge => self.install_forge_loader(profile).await?,
+                LoaderType::Quilt => self.install_quilt_loader(profile).await?,
+                LoaderType::NeoForge => self.install_neoforge_loader(profile).await?,
+                LoaderType::Paper => self.install_paper_server(profile, Project::Paper).await?,
+                LoaderType::Folia => self.install_paper_server(profile, Project::Folia).await?,
+                LoaderType::Purpur => self.install_purpur_server(profile).await?,
             },
             None => {
-                return Err(anyhow::anyhow!(
-                    "Vanilla Minecraft server installation is not yet supported. Minecraft version: {}",
-                    profile.version
-                ));
+                self.install_vanilla_server(profile).await?;
+                ServerLaunch::Jar("server.jar".into())
             }
-        }
+        };
+
+        let content_dir = self.server_dir.join(
+            profile
+                .loader
+                .as_ref()
+                .map_or("mods", |loader| loader.name.content_dir()),
+        );
+        tokio::fs::create_dir_all(&content_dir)
+            .await
+            .with_context(|| format!("Failed to create directory: {}", content_dir.display()))?;
 
         if let Some(modpack) = &profile.modpack {
-            self.install_modpack_files(modpack).await?;
+            self.install_modpack_files(modpack, &content_dir).await?;
         }
 
+        Ok(launch)
+    }
+
+    async fn install_vanilla_server(&self, profile: &MinecraftProfile) -> Result<()> {
+        tracing::info!("Installing vanilla Minecraft server {}...", profile.version);
+
+        let versions = MinecraftVersions::new().await?;
+        let version = versions.get_version(&profile.version).with_context(|| {
+            format!(
+                "Minecraft version {} not found in the version manifest",
+                profile.version
+            )
+        })?;
+
+        if version.version_type != VersionType::Release {
+            tracing::warn!(
+                "Minecraft {} is not a release version ({:?})",
+                version.id,
+                version.version_type
+            );
+        }
+
+        let details = versions.get_details(version).await?;
+        let server = details.downloads.server.with_context(|| {
+            format!(
+                "Minecraft {} does not provide a server download",
+                version.id
+            )
+        })?;
+
+        self.download_server_jar(
+            &server.url,
+            Checksum::Sha1(server.sha1.clone()),
+            Some(server.size),
+        )
+        .await?;
+
+        tracing::info!("Vanilla Minecraft server installed successfully");
         Ok(())
     }
 
+    async fn install_paper_server(
+        &self,
+        profile: &MinecraftProfile,
+        project: Project,
+    ) -> Result<ServerLaunch> {
+        tracing::info!("Installing {} server...", project);
+
+        let loader = profile.loader.as_ref().unwrap();
+        let build_id = loader
+            .version
+            .to_string()
+            .parse::<u32>()
+            .with_context(|| format!("Invalid {project} build number: {}", loader.version))?;
+
+        let build = PaperClient::with_client(network::client())
+            .get_build(project, &profile.version, build_id)
+            .await
+            .with_context(|| format!("Failed to get {project} build {build_id}"))?;
+        let download = build
+            .server_download()
+            .with_context(|| format!("{project} build {build_id} has no server download"))?;
+
+        self.download_server_jar(
+            &download.url,
+            Checksum::Sha256(download.checksums.sha256.clone()),
+            Some(download.size),
+        )
+        .await?;
+
+        tracing::info!("{} server installed successfully", project);
+        Ok(ServerLaunch::Jar("server.jar".into()))
+    }
+
+    async fn install_purpur_server(&self, profile: &MinecraftProfile) -> Result<ServerLaunch> {
+        tracing::info!("Installing Purpur server...");
+
+        let loader = profile.loader.as_ref().unwrap();
+        let build_id = loader.version.to_string();
+
+        let client = PurpurClient::with_client(network::client());
+        let build = client
+            .get_build(&profile.version, &build_id)
+            .await
+            .with_context(|| format!("Failed to get Purpur build {build_id}"))?;
+
+        if build.result != BuildResult::Success {
+            return Err(anyhow!("Purpur build {} did not succeed", build_id));
+        }
+
+        // Purpur only publishes an md5 digest for its builds.
+        let url = client.download_url(&profile.version, &build_id)?;
+        self.download_server_jar(url.as_str(), Checksum::Md5(build.md5), None)
+            .await?;
+
+        tracing::info!("Purpur server installed successfully");
+        Ok(ServerLaunch::Jar("server.jar".into()))
+    }
+
+    /// Downloads a server jar to `server.jar`, refusing it unless it matches the published
+    /// checksum and size.
+    async fn download_server_jar(
+        &self,
+        url: &str,
+        checksum: Checksum,
+        size: Option<u64>,
+    ) -> Result<()> {
+        self.downloader
+            .download_all(&[Download {
+                name: "server.jar".to_string(),
+                urls: vec![url.to_string()],
+                checksum: Some(checksum),
+                size,
+                path: self.server_dir.join("server.jar"),
+            }])
+            .await
+    }
+
     async fn install_fabric_loader(&self, profile: &MinecraftProfile) -> Result<()> {
         tracing::info!("Installing Fabric loader...");
 
@@ -46,42 +228,94 @@ impl MinecraftInstaller {
 
         tracing::debug!("Downloading Fabric server from: {}", fabric_installer_url);
 
-        let response = reqwest::get(&fabric_installer_url).await.with_context(|| {
-            format!("Failed to download Fabric server from {fabric_installer_url}")
-        })?;
+        // The Fabric meta API does not publish a checksum for the generated launcher jar.
+        network::download_file(
+            &fabric_installer_url,
+            None,
+            None,
+            &self.server_dir.join("server.jar"),
+        )
+        .await
+        .with_context(|| format!("Failed to download Fabric server from {fabric_installer_url}"))?;
 
-        if !response.status().is_success() {
-            return Err(anyhow::anyhow!(
-                "Failed to download Fabric server: HTTP {}",
-                response.status()
-            ));
-        }
+        tracing::info!("Fabric loader installed successfully");
+        Ok(())
+    }
+
+    async fn install_quilt_loader(&self, profile: &MinecraftProfile) -> Result<ServerLaunch> {
+        tracing::info!("Installing Quilt loader...");
 
-        let server_jar_path = self.server_dir.join("server.jar");
-        let bytes = response
-            .bytes()
+        let loader = profile.loader.as_ref().unwrap();
+        let launch_jar = QuiltClient::with_client(network::client())
+            .install_server(
+                &self.java_executable,
+                &profile.version,
+                &loader.version,
+                &self.server_dir,
+            )
             .await
-            .context("Failed to read Fabric server response")?;
+            .context("Failed to install Quilt server")?;
+
+        tracing::info!("Quilt loader installed successfully");
 
-        tokio::fs::write(&server_jar_path, bytes)
+        let launch_jar = launch_jar
+            .file_name()
+            .with_context(|| format!("Invalid launch jar path: {}", launch_jar.display()))?;
+        Ok(ServerLaunch::Jar(launch_jar.into()))
+    }
+
+    async fn install_forge_loader(&self, profile: &MinecraftProfile) -> Result<ServerLaunch> {
+        tracing::info!("Installing Forge loader...");
+
+        let loader = profile.loader.as_ref().unwrap();
+        let layout = ForgeClient::with_client(network::client())
+            .install_server(
+                &self.java_executable,
+                &profile.version,
+                &loader.version,
+                &self.server_dir,
+            )
             .await
-            .with_context(|| {
-                format!(
-                    "Failed to write server.jar to {}",
-                    server_jar_path.display()
-                )
-            })?;
+            .context("Failed to install Forge server")?;
 
-        tracing::info!("Fabric loader installed successfully");
-        Ok(())
+        tracing::info!("Forge loader installed successfully");
+        Ok(match layout {
+            ServerLayout::ArgsFile(args_file) => ServerLaunch::ArgsFile(args_file),
+            ServerLayout::Jar(jar) => ServerLaunch::Jar(jar),
+        })
     }
 
-    async fn install_modpack_files(&self, modpack: &crate::modpack::Modpack) -> Result<()> {
+    async fn install_neoforge_loader(&self, profile: &MinecraftProfile) -> Result<ServerLaunch> {
+        tracing::info!("Installing NeoForge loader...");
+
+        let loader = profile.loader.as_ref().unwrap();
+        let args_file = NeoForgeClient::with_client(network::client())
+            .install_server(&self.java_executable, &loader.version, &self.server_dir)
+            .await
+            .context("Failed to install NeoForge server")?;
+
+        tracing::info!("NeoForge loader installed successfully");
+        Ok(ServerLaunch::ArgsFile(args_file))
+    }
+
+    async fn install_modpack_files(
+        &self,
+        modpack: &crate::modpack::Modpack,
+        content_dir: &Path,
+    ) -> Result<()> {
         tracing::info!("Installing modpack files...");
 
         match modpack {
             crate::modpack::Modpack::Modrinth(modrinth_modpack) => {
-                self.install_modrinth_modpack(modrinth_modpack).await?;
+                self.install_modrinth_modpack(modrinth_modpack, content_dir)
+                    .await?;
+            }
+            crate::modpack::Modpack::CurseForge(curseforge_modpack) => {
+                self.install_curseforge_modpack(curseforge_modpack, content_dir)
+                    .await?;
+            }
+            crate::modpack::Modpack::Packwiz(packwiz_modpack) => {
+                self.install_packwiz_modpack(packwiz_modpack).await?;
             }
         }
 
@@ -91,53 +325,344 @@ impl MinecraftInstaller {
     async fn install_modrinth_modpack(
         &self,
         modpack: &crate::modpack::ModrinthModpack,
+        mods_dir: &Path,
     ) -> Result<()> {
         tracing::info!("Installing Modrinth modpack...");
 
-        let mods_dir = self.server_dir.join("mods");
-        tokio::fs::create_dir_all(&mods_dir)
-            .await
-            .with_context(|| format!("Failed to create mods directory: {}", mods_dir.display()))?;
+        let mut files = Vec::new();
+        let mut optional = Vec::new();
+        let mut client_only = Vec::new();
 
         for file in &modpack.files {
-            if let Some(downloads) = &file.downloads {
-                if let Some(download_url) = downloads.first() {
-                    tracing::debug!("Downloading mod: {} from {}", file.path, download_url);
+            match file.server_support() {
+                EnvironmentSupport::Required => files.push(file),
+                EnvironmentSupport::Optional => optional.push(file),
+                EnvironmentSupport::Unsupported => client_only.push(file.path.as_str()),
+            }
+        }
 
-                    let response = reqwest::get(download_url)
-                        .await
-                        .with_context(|| format!("Failed to download mod from {download_url}"))?;
+        let (selected, declined) = self.select_optional_files(optional)?;
+        files.extend(selected);
 
-                    if !response.status().is_success() {
-                        tracing::warn!(
-                            "Failed to download mod {}: HTTP {}",
-                            file.path,
-                            response.status()
-                        );
-                        continue;
-                    }
+        let mut downloads = Vec::new();
+        let mut invalid = Vec::new();
 
-                    let mod_filename = std::path::Path::new(&file.path)
-                        .file_name()
-                        .with_context(|| format!("Invalid mod file path: {}", file.path))?;
+        for file in files {
+            match Self::modrinth_download(file, mods_dir) {
+                Ok(download) => downloads.push(download),
+                Err(error) => {
+                    tracing::error!("{:#}", error);
+                    invalid.push(file.path.as_str());
+                }
+            }
+        }
 
-                    let mod_path = mods_dir.join(mod_filename);
+        if !invalid.is_empty() {
+            return Err(anyhow!(
+                "{} modpack files cannot be verified: {}",
+                invalid.len(),
+                invalid.join(", ")
+            ));
+        }
 
-                    let bytes = response
-                        .bytes()
-                        .await
-                        .context("Failed to read mod file response")?;
+        self.downloader
+            .download_all(&downloads)
+            .await
+            .context("Failed to download Modrinth files")?;
 
-                    tokio::fs::write(&mod_path, bytes).await.with_context(|| {
-                        format!("Failed to write mod file: {}", mod_path.display())
-                    })?;
+        // Per the mrpack spec, server-overrides/ wins over overrides/ for the same path.
+        for prefix in ["overrides", "server-overrides"] {
+            let written = extract_overrides(&modpack.archive, prefix, &self.server_dir)
+                .with_context(|| format!("Failed to apply Modrinth {prefix}/"))?;
 
-                    tracing::debug!("Downloaded mod: {}", file.path);
+            if !written.is_empty() {
+                tracing::info!("Applied {} files from {}/:", written.len(), prefix);
+                for path in &written {
+                    tracing::info!("  {}", path.display());
                 }
             }
         }
 
+        if !client_only.is_empty() {
+            tracing::info!(
+                "Skipped {} client-only files: {}",
+                client_only.len(),
+                client_only.join(", ")
+            );
+        }
+        if !declined.is_empty() {
+            tracing::info!(
+                "Skipped {} optional files: {}",
+                declined.len(),
+                declined
+                    .iter()
+                    .map(|file| file.path.as_str())
+                    .collect::<Vec<_>>()
+                    .join(", ")
+            );
+        }
+
         tracing::info!("Modrinth modpack installed successfully");
         Ok(())
     }
+
+    /// Every mirror in `downloads` is tried in order until one matches the declared hash and size.
+    fn modrinth_download(file: &ModrinthFile, mods_dir: &Path) -> Result<Download> {
+        let checksum = file
+            .checksum()
+            .with_context(|| format!("{} declares no sha1 or sha512 hash", file.path))?;
+
+        let mod_filename = Path::new(&file.path)
+            .file_name()
+            .with_context(|| format!("Invalid mod file path: {}", file.path))?;
+
+        let urls = file.downloads.clone().unwrap_or_default();
+        if urls.is_empty() {
+            return Err(anyhow!("{} has no download URLs", file.path));
+        }
+
+        Ok(Download {
+            name: file.path.clone(),
+            urls,
+            checksum: Some(checksum),
+            size: Some(file.file_size),
+            path: mods_dir.join(mod_filename),
+        })
+    }
+
+    /// Splits optional files into the ones to install and the ones to skip.
+    fn select_optional_files<'a>(
+        &self,
+        optional: Vec<&'a ModrinthFile>,
+    ) -> Result<(Vec<&'a ModrinthFile>, Vec<&'a ModrinthFile>)> {
+        if optional.is_empty() {
+            return Ok((optional, Vec::new()));
+        }
+
+        match self.optional_files {
+            OptionalFiles::Include => Ok((optional, Vec::new())),
+            OptionalFiles::Exclude => Ok((Vec::new(), optional)),
+            OptionalFiles::Prompt if !std::io::stdin().is_terminal() => {
+                tracing::warn!(
+                    "Not attached to a terminal, installing all {} optional files",
+                    optional.len()
+                );
+                Ok((optional, Vec::new()))
+            }
+            OptionalFiles::Prompt => {
+                let paths = optional
+                    .iter()
+                    .map(|file| file.path.clone())
+                    .collect::<Vec<_>>();
+
+                let chosen = inquire::MultiSelect::new("Optional server files to install:", paths)
+                    .with_all_selected_by_default()
+                    .raw_prompt()
+                    .context("Failed to read optional file selection")?
+                    .into_iter()
+                    .map(|option| option.index)
+                    .collect::<Vec<_>>();
+
+                let (selected, declined) = optional
+                    .into_iter()
+                    .enumerate()
+                    .partition::<Vec<_>, _>(|(index, _)| chosen.contains(index));
+
+                Ok((
+                    selected.into_iter().map(|(_, file)| file).collect(),
+                    declined.into_iter().map(|(_, file)| file).collect(),
+                ))
+            }
+        }
+    }
+
+    async fn install_curseforge_modpack(
+        &self,
+        modpack: &CurseForgeModpack,
+        mods_dir: &Path,
+    ) -> Result<()> {
+        tracing::info!("Installing CurseForge modpack...");
+
+        let file_ids = modpack
+            .files
+            .iter()
+            .filter(|file| {
+                if !file.required {
+                    tracing::info!(
+                        "Skipping optional CurseForge file {} of project {}",
+                        file.file_id,
+                        file.project_id
+                    );
+                }
+                file.required
+            })
+            .map(|file| file.file_id)
+            .collect::<Vec<_>>();
+
+        let files = self
+            .curseforge
+            .get_files(&file_ids)
+            .await
+            .context("Failed to resolve CurseForge files")?;
+
+        if files.len() != file_ids.len() {
+            let missing = file_ids
+                .iter()
+                .filter(|id| !files.iter().any(|file| file.id == **id))
+                .map(|id| id.to_string())
+                .collect::<Vec<_>>();
+
+            return Err(anyhow!(
+                "CurseForge did not return files: {}",
+                missing.join(", ")
+            ));
+        }
+
+        // Authors can opt out of third-party distribution, those files have no download URL.
+        let restricted = files
+            .iter()
+            .filter(|file| file.download_url.is_none())
+            .map(|file| format!("{} (project {})", file.file_name, file.mod_id))
+            .collect::<Vec<_>>();
+
+        if !restricted.is_empty() {
+            return Err(anyhow!(
+                "These CurseForge files do not allow third-party downloads and must be placed in {} by hand: {}",
+                mods_dir.display(),
+                restricted.join(", ")
+            ));
+        }
+
+        let downloads = files
+            .iter()
+            .filter(|file| {
+                // Resource packs and shaders share the manifest with mods but are not loaded by servers.
+                if !file.file_name.ends_with(".jar") {
+                    tracing::debug!("Skipping non-mod CurseForge file: {}", file.file_name);
+                }
+                file.file_name.ends_with(".jar")
+            })
+            .map(|file| Download {
+                name: file.file_name.clone(),
+                urls: file.download_url.iter().cloned().collect(),
+                checksum: file.checksum(),
+                size: Some(file.file_length),
+                path: mods_dir.join(&file.file_name),
+            })
+            .collect::<Vec<_>>();
+
+        self.downloader
+            .download_all(&downloads)
+            .await
+            .context("Failed to download CurseForge files")?;
+
+        if let Some(overrides) = &modpack.overrides {
+            let written = extract_overrides(&modpack.archive, overrides, &self.server_dir)
+                .context("Failed to apply CurseForge overrides")?;
+
+            tracing::info!("Applied {} override files", written.len());
+        }
+
+        tracing::info!("CurseForge modpack installed successfully");
+        Ok(())
+    }
+
+    /// Packwiz paths are relative to the pack root, so files land directly under the server
+    /// directory rather than in the loader's content directory.
+    async fn install_packwiz_modpack(&self, modpack: &PackwizModpack) -> Result<()> {
+        tracing::info!("Installing packwiz pack {}...", modpack.name);
+
+        let files = modpack
+            .files
+            .iter()
+            .filter(|file| {
+                if file.side == PackwizSide::Client {
+                    tracing::debug!("Skipping client-only file: {}", file.path);
+                }
+                file.side != PackwizSide::Client
+            })
+            .collect::<Vec<_>>();
+
+        let curseforge_ids = files
+            .iter()
+            .filter_map(|file| match file.download {
+                PackwizDownload::CurseForge { file_id, .. } => Some(file_id),
+                _ => None,
+            })
+            .collect::<Vec<_>>();
+
+        let curseforge_files = if curseforge_ids.is_empty() {
+            Vec::new()
+        } else {
+            self.curseforge
+                .get_files(&curseforge_ids)
+                .await
+                .context("Failed to resolve CurseForge files")?
+        };
+
+        let mut downloads = Vec::new();
+
+        for file in files {
+            let destination = self.server_dir.join(safe_relative_path(&file.path)?);
+
+            if file.preserve && destination.exists() {
+                tracing::debug!("Keeping existing file: {}", file.path);
+                continue;
+            }
+
+            if let Some(parent) = destination.parent() {
+                tokio::fs::create_dir_all(parent)
+                    .await
+                    .with_context(|| format!("Failed to create directory: {}", parent.display()))?;
+            }
+
+            let checksum = file.checksum()?;
+
+            let url = match &file.download {
+                PackwizDownload::Pack { file: relative } => {
+                    let bytes = modpack.source.read(relative).await?;
+                    checksum
+                        .verify(&bytes)
+                        .with_context(|| format!("{} failed verification", file.path))?;
+
+                    tokio::fs::write(&destination, bytes)
+                        .await
+                        .with_context(|| format!("Failed to write {}", destination.display()))?;
+
+                    tracing::debug!("Installed {}", file.path);
+                    continue;
+                }
+                PackwizDownload::Url { url } => url.clone(),
+                PackwizDownload::CurseForge {
+                    project_id,
+                    file_id,
+                } => curseforge_files
+                    .iter()
+                    .find(|info| info.id == *file_id)
+                    .and_then(|info| info.download_url.clone())
+                    .with_context(|| {
+                        format!(
+                            "CurseForge file {file_id} of project {project_id} cannot be downloaded, place {} by hand",
+                            file.path
+                        )
+                    })?,
+            };
+
+            downloads.push(Download {
+                name: file.path.clone(),
+                urls: vec![url],
+                checksum: Some(checksum),
+                size: None,
+                path: destination,
+            });
+        }
+
+        self.downloader
+            .download_all(&downloads)
+            .await
+            .context("Failed to download packwiz files")?;
+
+        tracing::info!("Packwiz pack installed successfully");
+        Ok(())
+    }
 }
@@ -0,0 +1,219 @@
+use std::{
+    fs::File,
+    hash::{Hash, Hasher},
+    io::BufReader,
+    path::{Path, PathBuf},
+};
+
+use anyhow::{Context, Result, anyhow};
+use reqwest::{StatusCode, Url};
+use serde::{Deserialize, Serialize};
+use versions::Versioning;
+use zip::ZipArchive;
+
+use super::{FromStr, Importable, Loader, LoaderType};
+use crate::{checksum::Checksum, network};
+
+#[derive(Serialize, Deserialize, Debug, Hash, Clone)]
+pub struct CurseForgeModLoader {
+    pub id: String,
+    #[serde(default)]
+    pub primary: bool,
+}
+
+#[derive(Serialize, Deserialize, Debug, Hash, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct CurseForgeMinecraft {
+    pub version: String,
+    pub mod_loaders: Vec<CurseForgeModLoader>,
+}
+
+#[derive(Serialize, Deserialize, Debug, Hash, Clone)]
+pub struct CurseForgeFile {
+    #[serde(rename = "projectID")]
+    pub project_id: u64,
+    #[serde(rename = "fileID")]
+    pub file_id: u64,
+    #[serde(default = "default_required")]
+    pub required: bool,
+}
+
+fn default_required() -> bool {
+    true
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct CurseForgeModpack {
+    pub minecraft: CurseForgeMinecraft,
+    pub manifest_type: String,
+    pub manifest_version: u32,
+    pub name: String,
+    pub version: Option<String>,
+    pub author: Option<String>,
+    pub files: Vec<CurseForgeFile>,
+    pub overrides: Option<String>,
+    /// The archive the manifest was read from, its `overrides/` folder is applied at install time.
+    #[serde(skip)]
+    pub archive: PathBuf,
+}
+
+impl Hash for CurseForgeModpack {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        self.minecraft.hash(state);
+        self.name.hash(state);
+        self.version.hash(state);
+        self.files.hash(state);
+        self.overrides.hash(state);
+    }
+}
+
+impl CurseForgeModpack {
+    pub fn get_minecraft_version(&self) -> Result<Versioning> {
+        Versioning::new(&self.minecraft.version).with_context(|| {
+            format!(
+                "manifest.json has an invalid Minecraft version '{}'",
+                self.minecraft.version
+            )
+        })
+    }
+
+    /// Maps the primary `<loader>-<version>` entry, e.g. `forge-47.2.0`, onto a [`Loader`].
+    pub fn get_loader(&self) -> Option<Loader> {
+        let mod_loader = self
+            .minecraft
+            .mod_loaders
+            .iter()
+            .find(|loader| loader.primary)
+            .or_else(|| self.minecraft.mod_loaders.first())?;
+
+        let (name, version) = mod_loader.id.split_once('-')?;
+
+        Some(Loader {
+            name: LoaderType::from_str(name).ok()?,
+            version: Versioning::new(version)?,
+        })
+    }
+}
+
+impl Importable<CurseForgeModpack> for CurseForgeModpack {
+    fn import<P: AsRef<Path>>(path: P) -> Result<Self> {
+        let path = path.as_ref();
+
+        let file = File::open(path)
+            .with_context(|| format!("Failed to open CurseForge modpack: {}", path.display()))?;
+        let reader = BufReader::new(file);
+        let mut archive = ZipArchive::new(reader)
+            .with_context(|| format!("Failed to read CurseForge modpack: {}", path.display()))?;
+
+        let manifest = archive
+            .by_name("manifest.json")
+            .context("CurseForge modpack has no manifest.json")?;
+
+        let mut modpack: Self =
+            serde_json::from_reader(manifest).context("Failed to parse manifest.json")?;
+
+        if modpack.manifest_type != "minecraftModpack" {
+            return Err(anyhow!(
+                "Unsupported CurseForge manifest type: {}",
+                modpack.manifest_type
+            ));
+        }
+
+        modpack.archive = path.to_path_buf();
+
+        Ok(modpack)
+    }
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct CurseForgeFileHash {
+    pub value: String,
+    pub algo: u8,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct CurseForgeFileInfo {
+    pub id: u64,
+    pub mod_id: u64,
+    pub file_name: String,
+    pub download_url: Option<String>,
+    pub file_length: u64,
+    #[serde(default)]
+    pub hashes: Vec<CurseForgeFileHash>,
+}
+
+impl CurseForgeFileInfo {
+    /// CurseForge publishes sha1 (algo 1) and md5 (algo 2) digests, sha1 is preferred.
+    pub fn checksum(&self) -> Option<Checksum> {
+        let find = |algo| self.hashes.iter().find(|hash| hash.algo == algo);
+
+        find(1)
+            .map(|hash| Checksum::Sha1(hash.value.clone()))
+            .or_else(|| find(2).map(|hash| Checksum::Md5(hash.value.clone())))
+    }
+}
+
+#[derive(Deserialize)]
+struct CurseForgeResponse<T> {
+    data: T,
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+struct CurseForgeFilesRequest<'a> {
+    file_ids: &'a [u64],
+}
+
+/// Client for the CurseForge core API, the base URL can point at a mirror or a local mock.
+#[derive(Clone)]
+pub struct CurseForgeClient {
+    base_url: Url,
+    api_key: Option<String>,
+}
+
+impl CurseForgeClient {
+    pub fn new(base_url: Url, api_key: Option<String>) -> Self {
+        Self { base_url, api_key }
+    }
+
+    pub async fn get_files(&self, file_ids: &[u64]) -> Result<Vec<CurseForgeFileInfo>> {
+        let url = self.base_url.join("v1/mods/files")?;
+
+        tracing::debug!("Resolving {} CurseForge files via {}", file_ids.len(), url);
+
+        let response = network::send(|client| {
+            let request = client
+                .post(url.clone())
+                .json(&CurseForgeFilesRequest { file_ids });
+
+            match &self.api_key {
+                Some(api_key) => request.header("x-api-key", api_key),
+                None => request,
+            }
+        })
+        .await
+        .map_err(|error| {
+            let forbidden = error
+                .downcast_ref::<reqwest::Error>()
+                .and_then(reqwest::Error::status)
+                == Some(StatusCode::FORBIDDEN);
+
+            if forbidden {
+                anyhow!(
+                    "CurseForge API rejected the request, set CURSEFORGE_API_KEY to a valid key"
+                )
+            } else {
+                error.context("Failed to reach the CurseForge API")
+            }
+        })?;
+
+        let body = response
+            .json::<CurseForgeResponse<Vec<CurseForgeFileInfo>>>()
+            .await
+            .context("Failed to parse CurseForge files")?;
+
+        Ok(body.data)
+    }
+}
@@ -1,19 +1,37 @@
+mod curseforge;
 mod modrinth;
+mod packwiz;
 
 use std::{
     fmt,
+    fs::File,
     hash::{Hash, Hasher},
-    path::Path,
+    io::BufReader,
+    path::{Component, Path, PathBuf},
     str::FromStr,
 };
 
 use anyhow::{Context, Result, anyhow};
 
+use feather_fabric::FabricClient;
+use feather_forge::ForgeClient;
+use feather_neoforge::NeoForgeClient;
+use feather_paper::{PaperClient, Project};
+use feather_purpur::PurpurClient;
+use feather_quilt::QuiltClient;
 use rustc_hash::FxHasher;
 use serde::{Deserialize, Serialize, de::DeserializeOwned};
 
-pub use modrinth::ModrinthModpack;
+pub use curseforge::{CurseForgeClient, CurseForgeModpack};
+pub use modrinth::{EnvironmentSupport, ModrinthFile, ModrinthModpack};
+pub use packwiz::{PackwizDownload, PackwizModpack, PackwizSide, PackwizSource};
 use versions::Versioning;
+use zip::ZipArchive;
+
+use crate::{
+    cli::LoaderVersionSelection,
+    network::{self, MinecraftVersions},
+};
 
 pub trait Importable<T> {
     fn import<P: AsRef<Path>>(path: P) -> Result<T>
@@ -24,29 +42,101 @@ pub trait Importable<T> {
 #[derive(Serialize, Deserialize, Debug, Hash, Clone)]
 pub enum Modpack {
     Modrinth(ModrinthModpack),
+    CurseForge(CurseForgeModpack),
+    Packwiz(PackwizModpack),
 }
 
 impl Modpack {
     pub fn try_import<P: AsRef<Path>>(path: P) -> Result<Self> {
-        match ModrinthModpack::import(&path) {
+        let modrinth_error = match ModrinthModpack::import(&path) {
             Ok(modpack) => return Ok(Modpack::Modrinth(modpack)),
-            Err(modrinth_error) => {
-                tracing::error!("Debug: Failed to import as Modrinth: {:?}", modrinth_error);
+            Err(e) => e,
+        };
+        tracing::debug!("Failed to import as Modrinth: {:?}", modrinth_error);
 
-                Err(modrinth_error).context(format!(
-                    "Attempted to import '{}' as Modrinth format failed.",
-                    path.as_ref().display()
-                ))?;
-            }
-        }
+        let curseforge_error = match CurseForgeModpack::import(&path) {
+            Ok(modpack) => return Ok(Modpack::CurseForge(modpack)),
+            Err(e) => e,
+        };
+        tracing::debug!("Failed to import as CurseForge: {:?}", curseforge_error);
 
         Err(anyhow::anyhow!(
-            "Could not import file '{}' as any known modpack type.",
-            path.as_ref().display()
+            "Could not import file '{}' as any known modpack type.\nModrinth: {:#}\nCurseForge: {:#}",
+            path.as_ref().display(),
+            modrinth_error,
+            curseforge_error
         ))
     }
 }
 
+/// Validates a path taken from a modpack so it cannot escape the server directory through
+/// `..` components or absolute paths.
+pub fn safe_relative_path(path: &str) -> Result<PathBuf> {
+    let relative = PathBuf::from(path);
+
+    if relative.as_os_str().is_empty()
+        || !relative
+            .components()
+            .all(|component| matches!(component, Component::Normal(_) | Component::CurDir))
+    {
+        return Err(anyhow!(
+            "Refusing to write '{}' outside of the server directory",
+            path
+        ));
+    }
+
+    Ok(relative)
+}
+
+/// Copies every file under `prefix/` in a modpack archive into `destination`.
+///
+/// Entries that would escape `destination`, such as `../` or absolute paths, are refused.
+/// Returns the written paths relative to `destination`.
+pub fn extract_overrides(archive: &Path, prefix: &str, destination: &Path) -> Result<Vec<PathBuf>> {
+    let file = File::open(archive)
+        .with_context(|| format!("Failed to open modpack archive: {}", archive.display()))?;
+    let mut archive = ZipArchive::new(BufReader::new(file))
+        .with_context(|| format!("Failed to read modpack archive: {}", archive.display()))?;
+
+    let mut written = Vec::new();
+
+    for index in 0..archive.len() {
+        let mut entry = archive.by_index(index)?;
+
+        let Some(relative) = entry
+            .name()
+            .strip_prefix(prefix)
+            .and_then(|rest| rest.strip_prefix('/'))
+            .filter(|rest| !rest.is_empty())
+        else {
+            continue;
+        };
+        let relative = safe_relative_path(relative)?;
+
+        let target = destination.join(&relative);
+
+        if entry.is_dir() {
+            std::fs::create_dir_all(&target)
+                .with_context(|| format!("Failed to create directory: {}", target.display()))?;
+            continue;
+        }
+
+        if let Some(parent) = target.parent() {
+            std::fs::create_dir_all(parent)
+                .with_context(|| format!("Failed to create directory: {}", parent.display()))?;
+        }
+
+        let mut output = File::create(&target)
+            .with_context(|| format!("Failed to create override: {}", target.display()))?;
+        std::io::copy(&mut entry, &mut output)
+            .with_context(|| format!("Failed to write override: {}", target.display()))?;
+
+        written.push(relative);
+    }
+
+    Ok(written)
+}
+
 #[derive(Serialize, Deserialize, Debug, Clone)]
 pub struct MinecraftProfile {
     pub version: Versioning,
@@ -83,36 +173,198 @@ impl MinecraftProfile {
         format!("{:x}", state.finish())
     }
 
+    /// Imports a modpack archive, a packwiz directory or a hosted packwiz `pack.toml`.
+    #[tracing::instrument(level = "debug", skip_all)]
+    pub async fn try_import(location: &str) -> Result<Self> {
+        tracing::debug!("Importing Minecraft profile from {}", location);
+
+        let modpack = match PackwizSource::detect(location) {
+            Some(source) => Modpack::Packwiz(
+                PackwizModpack::load(source)
+                    .await
+                    .with_context(|| format!("Failed to import packwiz pack '{location}'"))?,
+            ),
+            None => Modpack::try_import(location)?,
+        };
+
+        match modpack {
+            Modpack::Modrinth(ref modrinth_modpack) => {
+                let profile = MinecraftProfile {
+                    version: modrinth_modpack.get_minecraft_version(),
+                    loader: modrinth_modpack.get_loader(),
+                    modpack: Some(modpack),
+                };
+                Ok(profile)
+            }
+            Modpack::CurseForge(ref curseforge_modpack) => {
+                let profile = MinecraftProfile {
+                    version: curseforge_modpack.get_minecraft_version()?,
+                    loader: curseforge_modpack.get_loader(),
+                    modpack: Some(modpack),
+                };
+                Ok(profile)
+            }
+            Modpack::Packwiz(ref packwiz_modpack) => {
+                let profile = MinecraftProfile {
+                    version: packwiz_modpack.get_minecraft_version()?,
+                    loader: packwiz_modpack.get_loader(),
+                    modpack: Some(modpack),
+                };
+                Ok(profile)
+            }
+        }
+    }
+
+    /// Builds a profile without a modpack, resolving the loader version against its meta API.
     #[tracing::instrument(level = "debug", skip_all)]
-    pub fn try_import<T: AsRef<Path>>(file: T) -> Result<Self> {
-        tracing::debug!(
-            "Importing Minecraft profile from {}",
-            file.as_ref().display()
-        );
-
-        match Modpack::try_import(file) {
-            Ok(modpack) => match modpack {
-                Modpack::Modrinth(ref modrinth_modpack) => {
-                    let profile = MinecraftProfile {
-                        version: modrinth_modpack.get_minecraft_version(),
-                        loader: modrinth_modpack.get_loader(),
-                        modpack: Some(modpack),
-                    };
-                    Ok(profile)
-                }
-            },
-            Err(e) => Err(e),
+    pub async fn resolve(
+        version: &Versioning,
+        loader: Option<&LoaderType>,
+        loader_version: &LoaderVersionSelection,
+    ) -> Result<Self> {
+        tracing::debug!("Resolving Minecraft profile for {}", version);
+
+        let versions = MinecraftVersions::new().await?;
+        if versions.get_version(version).is_none() {
+            return Err(anyhow!(
+                "Minecraft version {} not found in the version manifest",
+                version
+            ));
         }
+
+        let loader = match loader {
+            Some(name) => Some(Loader {
+                version: Self::resolve_loader_version(version, name, loader_version).await?,
+                name: name.clone(),
+            }),
+            None => None,
+        };
+
+        Ok(MinecraftProfile {
+            version: version.clone(),
+            loader,
+            modpack: None,
+        })
+    }
+
+    async fn resolve_loader_version(
+        version: &Versioning,
+        loader: &LoaderType,
+        selection: &LoaderVersionSelection,
+    ) -> Result<Versioning> {
+        let candidates = match loader {
+            LoaderType::Fabric => FabricClient::with_client(network::client())
+                .get_loader_versions(version)
+                .await
+                .with_context(|| format!("Failed to get Fabric loader versions for {version}"))?
+                .into_iter()
+                .map(|v| (v.version, v.stable))
+                .collect::<Vec<_>>(),
+            LoaderType::Quilt => QuiltClient::with_client(network::client())
+                .get_loader_versions(version)
+                .await
+                .with_context(|| format!("Failed to get Quilt loader versions for {version}"))?
+                .into_iter()
+                .map(|v| {
+                    let stable = v.is_stable();
+                    (v.version, stable)
+                })
+                .collect::<Vec<_>>(),
+            LoaderType::Forge => ForgeClient::with_client(network::client())
+                .get_loader_versions(version)
+                .await
+                .with_context(|| format!("Failed to get Forge versions for {version}"))?
+                .into_iter()
+                .map(|v| (v.version, v.stable))
+                .collect::<Vec<_>>(),
+            LoaderType::NeoForge => NeoForgeClient::with_client(network::client())
+                .get_loader_versions(version)
+                .await
+                .with_context(|| format!("Failed to get NeoForge versions for {version}"))?
+                .into_iter()
+                .map(|v| (v.version, v.stable))
+                .collect::<Vec<_>>(),
+            LoaderType::Paper | LoaderType::Folia => {
+                let project = if *loader == LoaderType::Folia {
+                    Project::Folia
+                } else {
+                    Project::Paper
+                };
+
+                PaperClient::with_client(network::client())
+                    .get_builds(project, version)
+                    .await
+                    .with_context(|| format!("Failed to get {project} builds for {version}"))?
+                    .into_iter()
+                    .filter_map(|build| {
+                        let stable = build.is_stable();
+                        Some((Versioning::new(build.id.to_string())?, stable))
+                    })
+                    .collect::<Vec<_>>()
+            }
+            LoaderType::Purpur => {
+                let builds = PurpurClient::with_client(network::client())
+                    .get_builds(version)
+                    .await
+                    .with_context(|| format!("Failed to get Purpur builds for {version}"))?;
+
+                builds
+                    .all
+                    .iter()
+                    .rev()
+                    .filter_map(|build| Some((Versioning::new(build)?, *build == builds.latest)))
+                    .collect::<Vec<_>>()
+            }
+        };
+
+        let resolved = match selection {
+            LoaderVersionSelection::Latest => candidates.into_iter().next(),
+            LoaderVersionSelection::LatestStable => {
+                candidates.into_iter().find(|(_, stable)| *stable)
+            }
+            LoaderVersionSelection::Exact(wanted) => {
+                candidates.into_iter().find(|(v, _)| v == wanted)
+            }
+        };
+
+        let (resolved, _) = resolved.with_context(|| {
+            format!("No {loader} loader version matching {selection:?} for Minecraft {version}")
+        })?;
+
+        tracing::info!("Resolved {} loader version {}", loader, resolved);
+        Ok(resolved)
     }
 }
 
-#[derive(Serialize, Deserialize, Debug, Hash, Clone)]
+#[derive(Serialize, Deserialize, Debug, Hash, Clone, PartialEq, Eq)]
 pub enum LoaderType {
     Fabric,
     // Babric,
-    // Forge,
-    // Quilt,
-    // NeoForge,
+    Forge,
+    Quilt,
+    NeoForge,
+    Paper,
+    Purpur,
+    Folia,
+}
+
+impl LoaderType {
+    /// Plugin servers load Bukkit plugins rather than mods.
+    pub fn is_plugin_server(&self) -> bool {
+        matches!(
+            self,
+            LoaderType::Paper | LoaderType::Purpur | LoaderType::Folia
+        )
+    }
+
+    /// Directory, relative to the server directory, that mods or plugins are installed into.
+    pub fn content_dir(&self) -> &'static str {
+        if self.is_plugin_server() {
+            "plugins"
+        } else {
+            "mods"
+        }
+    }
 }
 
 impl fmt::Display for LoaderType {
@@ -122,9 +374,12 @@ impl fmt::Display for LoaderType {
             "{}",
             match self {
                 LoaderType::Fabric => "fabric",
-                // LoaderType::Forge => "forge",
-                // LoaderType::Quilt => "quilt",
-                // LoaderType::NeoForge => "neoforge",
+                LoaderType::Forge => "forge",
+                LoaderType::Quilt => "quilt",
+                LoaderType::NeoForge => "neoforge",
+                LoaderType::Paper => "paper",
+                LoaderType::Purpur => "purpur",
+                LoaderType::Folia => "folia",
             }
         )
     }
@@ -136,9 +391,12 @@ impl FromStr for LoaderType {
     fn from_str(s: &str) -> Result<Self> {
         Ok(match s {
             "fabric" | "fabric-loader" => LoaderType::Fabric,
-            // "forge" => LoaderType::Forge,
-            // "quilt" => LoaderType::Quilt,
-            // "neoforge" => LoaderType::NeoForge,
+            "forge" => LoaderType::Forge,
+            "quilt" | "quilt-loader" => LoaderType::Quilt,
+            "neoforge" => LoaderType::NeoForge,
+            "paper" => LoaderType::Paper,
+            "purpur" => LoaderType::Purpur,
+            "folia" => LoaderType::Folia,
             _ => return Err(anyhow!("Unknown mod loader: {}", s)),
         })
     }
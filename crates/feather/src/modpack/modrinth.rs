@@ -2,21 +2,21 @@ use std::{
     fs::File,
     hash::{Hash, Hasher},
     io::BufReader,
-    path::Path,
+    path::{Path, PathBuf},
 };
 
 use anyhow::{Context, Result};
 use serde::{Deserialize, Serialize};
 use std::collections::HashMap;
-use tempfile::tempdir;
 use versions::Versioning;
 use zip::ZipArchive;
 
 use super::{FromStr, Importable, Loader, LoaderType};
+use crate::checksum::Checksum;
 
-#[derive(Serialize, Deserialize, Debug, Hash, Clone)]
+#[derive(Serialize, Deserialize, Debug, Hash, Clone, Copy, PartialEq, Eq)]
 #[serde(rename_all = "snake_case")]
-enum EnvironmentSupport {
+pub enum EnvironmentSupport {
     Required,
     Optional,
     Unsupported,
@@ -24,8 +24,8 @@ enum EnvironmentSupport {
 
 #[derive(Serialize, Deserialize, Debug, Hash, Clone)]
 pub struct MinecraftEnvironment {
-    client: EnvironmentSupport,
-    server: EnvironmentSupport,
+    pub client: EnvironmentSupport,
+    pub server: EnvironmentSupport,
 }
 
 #[derive(Serialize, Deserialize, Debug, Clone)]
@@ -38,6 +38,27 @@ pub struct ModrinthFile {
     pub env: Option<MinecraftEnvironment>,
 }
 
+impl ModrinthFile {
+    /// Files without an `env` block are required on both sides.
+    pub fn server_support(&self) -> EnvironmentSupport {
+        self.env
+            .as_ref()
+            .map_or(EnvironmentSupport::Required, |env| env.server)
+    }
+
+    /// The strongest digest the index declares, sha512 is preferred over sha1.
+    pub fn checksum(&self) -> Option<Checksum> {
+        self.hashes
+            .get("sha512")
+            .map(|hash| Checksum::Sha512(hash.to_ascii_lowercase()))
+            .or_else(|| {
+                self.hashes
+                    .get("sha1")
+                    .map(|hash| Checksum::Sha1(hash.to_ascii_lowercase()))
+            })
+    }
+}
+
 impl Hash for ModrinthFile {
     fn hash<H: Hasher>(&self, state: &mut H) {
         self.path.hash(state);
@@ -61,6 +82,10 @@ pub struct ModrinthModpack {
     pub summary: Option<String>,
     pub files: Vec<ModrinthFile>,
     pub dependencies: HashMap<String, String>,
+    /// The archive the index was read from, its `overrides/` and `server-overrides/` folders
+    /// are applied at install time.
+    #[serde(skip)]
+    pub archive: PathBuf,
 }
 
 impl Hash for ModrinthModpack {
@@ -103,9 +128,8 @@ impl ModrinthModpack {
 }
 
 impl Importable<ModrinthModpack> for ModrinthModpack {
-    // TODO: Make async including the extraction
+    // TODO: Make async
     fn import<P: AsRef<Path>>(path: P) -> Result<Self> {
-        // PERF: Import directly from ZIP file without extracting to temp dir
         let path = path.as_ref();
 
         let file = File::open(path)
@@ -114,18 +138,13 @@ impl Importable<ModrinthModpack> for ModrinthModpack {
         let mut archive = ZipArchive::new(reader)
             .with_context(|| format!("Failed to read .mrpack file: {}", path.display()))?;
 
-        let temp_dir = tempdir().context("Failed to create temporary directory")?;
-        archive.extract(temp_dir.path()).with_context(|| {
-            format!(
-                "Failed to extract .mrpack file to temp dir: {}",
-                temp_dir.path().display()
-            )
-        })?;
-
-        let index_path = temp_dir.path().join("modrinth.index.json");
+        let index = archive
+            .by_name("modrinth.index.json")
+            .context(".mrpack file has no modrinth.index.json")?;
 
-        let index_file = File::open(&index_path)?;
-        let modpack: Self = serde_json::from_reader(index_file)?;
+        let mut modpack: Self =
+            serde_json::from_reader(index).context("Failed to parse modrinth.index.json")?;
+        modpack.archive = path.to_path_buf();
 
         Ok(modpack)
     }
@@ -0,0 +1,380 @@
+use std::{collections::HashMap, path::PathBuf};
+
+use anyhow::{Context, Result, anyhow};
+use reqwest::Url;
+use serde::{Deserialize, Serialize};
+use versions::Versioning;
+
+use super::{FromStr, Loader, LoaderType, safe_relative_path};
+use crate::{checksum::Checksum, network};
+
+/// Where a packwiz pack is read from, either a local checkout or its hosted `pack.toml`.
+#[derive(Serialize, Deserialize, Debug, Hash, Clone)]
+pub enum PackwizSource {
+    Local(PathBuf),
+    Remote(String),
+}
+
+impl PackwizSource {
+    /// Recognises `http(s)://` URLs, directories containing `pack.toml` and `pack.toml` itself.
+    pub fn detect(location: &str) -> Option<Self> {
+        if location.starts_with("http://") || location.starts_with("https://") {
+            let url = if location.ends_with(".toml") {
+                location.to_string()
+            } else {
+                format!("{}/pack.toml", location.trim_end_matches('/'))
+            };
+            return Some(PackwizSource::Remote(url));
+        }
+
+        let path = PathBuf::from(location);
+        if path.is_dir() && path.join("pack.toml").is_file() {
+            return Some(PackwizSource::Local(path));
+        }
+        if path.file_name().is_some_and(|name| name == "pack.toml") && path.is_file() {
+            return Some(PackwizSource::Local(path.parent()?.to_path_buf()));
+        }
+
+        None
+    }
+
+    /// Reads a file relative to the directory holding `pack.toml`.
+    ///
+    /// Paths come from the pack itself, so anything that would leave that directory, such as
+    /// `..`, absolute paths or other hosts, is refused.
+    pub async fn read(&self, relative: &str) -> Result<Vec<u8>> {
+        let relative = safe_relative_path(relative)
+            .with_context(|| format!("Pack refers to an unsafe path '{relative}'"))?;
+
+        match self {
+            PackwizSource::Local(dir) => {
+                let path = dir.join(relative);
+                tokio::fs::read(&path)
+                    .await
+                    .with_context(|| format!("Failed to read {}", path.display()))
+            }
+            PackwizSource::Remote(pack_url) => {
+                let pack_dir = Url::parse(pack_url)?.join("./")?;
+                let url = pack_dir.join(&relative.to_string_lossy())?;
+
+                // `Url::join` also follows schemes and backslashes a path component can hold.
+                if !url.as_str().starts_with(pack_dir.as_str()) {
+                    return Err(anyhow!("Pack refers to {} outside of {}", url, pack_dir));
+                }
+
+                tracing::debug!("Fetching {}", url);
+
+                let response = network::send(|client| client.get(url.clone()))
+                    .await
+                    .with_context(|| format!("Failed to fetch {url}"))?;
+
+                Ok(response
+                    .bytes()
+                    .await
+                    .with_context(|| format!("Failed to read {url}"))?
+                    .to_vec())
+            }
+        }
+    }
+}
+
+#[derive(Serialize, Deserialize, Debug, Hash, Clone, Copy, PartialEq, Eq, Default)]
+#[serde(rename_all = "snake_case")]
+pub enum PackwizSide {
+    Client,
+    Server,
+    #[default]
+    Both,
+}
+
+/// Where the bytes of a pack file come from.
+#[derive(Serialize, Deserialize, Debug, Hash, Clone)]
+pub enum PackwizDownload {
+    /// A file committed to the pack itself, such as a config, relative to `pack.toml`.
+    Pack {
+        file: String,
+    },
+    Url {
+        url: String,
+    },
+    /// `metadata:curseforge` files are resolved through the CurseForge API.
+    CurseForge {
+        project_id: u64,
+        file_id: u64,
+    },
+}
+
+#[derive(Serialize, Deserialize, Debug, Hash, Clone)]
+pub struct PackwizFile {
+    /// Destination relative to the pack root, which maps onto the server directory.
+    pub path: String,
+    pub side: PackwizSide,
+    pub hash_format: String,
+    pub hash: String,
+    /// Files the server may have changed, they are only written when missing.
+    pub preserve: bool,
+    pub download: PackwizDownload,
+}
+
+impl PackwizFile {
+    pub fn checksum(&self) -> Result<Checksum> {
+        Checksum::from_packwiz(&self.hash_format, &self.hash)
+    }
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "kebab-case")]
+struct PackToml {
+    name: String,
+    version: Option<String>,
+    index: IndexReference,
+    versions: HashMap<String, String>,
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "kebab-case")]
+struct IndexReference {
+    file: String,
+    hash_format: String,
+    hash: String,
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "kebab-case")]
+struct IndexToml {
+    hash_format: String,
+    #[serde(default)]
+    files: Vec<IndexEntry>,
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "kebab-case")]
+struct IndexEntry {
+    file: String,
+    hash: String,
+    hash_format: Option<String>,
+    #[serde(default)]
+    metafile: bool,
+    #[serde(default)]
+    preserve: bool,
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "kebab-case")]
+struct MetaToml {
+    filename: String,
+    #[serde(default)]
+    side: PackwizSide,
+    download: MetaDownload,
+    update: Option<MetaUpdate>,
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "kebab-case")]
+struct MetaDownload {
+    url: Option<String>,
+    hash_format: String,
+    hash: String,
+    #[serde(default)]
+    mode: String,
+}
+
+#[derive(Deserialize)]
+struct MetaUpdate {
+    curseforge: Option<CurseForgeUpdate>,
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "kebab-case")]
+struct CurseForgeUpdate {
+    file_id: u64,
+    project_id: u64,
+}
+
+#[derive(Serialize, Deserialize, Debug, Hash, Clone)]
+pub struct PackwizModpack {
+    pub name: String,
+    pub version: Option<String>,
+    pub versions: Vec<(String, String)>,
+    pub files: Vec<PackwizFile>,
+    pub source: PackwizSource,
+}
+
+impl PackwizModpack {
+    pub fn get_minecraft_version(&self) -> Result<Versioning> {
+        self.versions
+            .iter()
+            .find(|(key, _)| key == "minecraft")
+            .and_then(|(_, version)| Versioning::new(version))
+            .ok_or_else(|| anyhow!("pack.toml does not declare a Minecraft version"))
+    }
+
+    pub fn get_loader(&self) -> Option<Loader> {
+        self.versions.iter().find_map(|(key, version)| {
+            Some(Loader {
+                name: LoaderType::from_str(key).ok()?,
+                version: Versioning::new(version)?,
+            })
+        })
+    }
+
+    /// Reads `pack.toml`, its index and every metafile, checking each against the hash
+    /// recorded one level up.
+    pub async fn load(source: PackwizSource) -> Result<Self> {
+        let pack: PackToml = parse_toml(&source.read("pack.toml").await?, "pack.toml")?;
+
+        let index_bytes = source.read(&pack.index.file).await?;
+        Checksum::from_packwiz(&pack.index.hash_format, &pack.index.hash)?
+            .verify(&index_bytes)
+            .with_context(|| format!("{} failed verification", pack.index.file))?;
+
+        let index: IndexToml = parse_toml(&index_bytes, &pack.index.file)?;
+
+        // Index entries are relative to the directory holding the index.
+        let index_dir = pack
+            .index
+            .file
+            .rsplit_once('/')
+            .map_or(String::new(), |(dir, _)| format!("{dir}/"));
+
+        let mut files = Vec::with_capacity(index.files.len());
+
+        for entry in index.files {
+            let hash_format = entry
+                .hash_format
+                .clone()
+                .unwrap_or_else(|| index.hash_format.clone());
+            let location = format!("{index_dir}{}", entry.file);
+
+            if !entry.metafile {
+                files.push(PackwizFile {
+                    path: entry.file,
+                    side: PackwizSide::Both,
+                    hash_format,
+                    hash: entry.hash,
+                    preserve: entry.preserve,
+                    download: PackwizDownload::Pack { file: location },
+                });
+                continue;
+            }
+
+            let meta_bytes = source.read(&location).await?;
+            Checksum::from_packwiz(&hash_format, &entry.hash)?
+                .verify(&meta_bytes)
+                .with_context(|| format!("{} failed verification", entry.file))?;
+
+            let meta: MetaToml = parse_toml(&meta_bytes, &entry.file)?;
+
+            let download = match (meta.download.mode.as_str(), meta.download.url) {
+                ("" | "url", Some(url)) => PackwizDownload::Url { url },
+                ("metadata:curseforge", _) => {
+                    let curseforge = meta
+                        .update
+                        .and_then(|update| update.curseforge)
+                        .with_context(|| {
+                            format!("{} has no [update.curseforge] section", entry.file)
+                        })?;
+                    PackwizDownload::CurseForge {
+                        project_id: curseforge.project_id,
+                        file_id: curseforge.file_id,
+                    }
+                }
+                (mode, _) => {
+                    return Err(anyhow!(
+                        "{} uses unsupported download mode '{}'",
+                        entry.file,
+                        mode
+                    ));
+                }
+            };
+
+            // The jar lands next to its metafile, e.g. mods/sodium.pw.toml -> mods/sodium.jar.
+            let path = match entry.file.rsplit_once('/') {
+                Some((dir, _)) => format!("{dir}/{}", meta.filename),
+                None => meta.filename,
+            };
+
+            files.push(PackwizFile {
+                path,
+                side: meta.side,
+                hash_format: meta.download.hash_format,
+                hash: meta.download.hash,
+                preserve: entry.preserve,
+                download,
+            });
+        }
+
+        let mut versions = pack.versions.into_iter().collect::<Vec<_>>();
+        versions.sort();
+
+        Ok(Self {
+            name: pack.name,
+            version: pack.version,
+            versions,
+            files,
+            source,
+        })
+    }
+}
+
+fn parse_toml<T: serde::de::DeserializeOwned>(bytes: &[u8], name: &str) -> Result<T> {
+    let content = std::str::from_utf8(bytes).with_context(|| format!("{name} is not UTF-8"))?;
+    toml::from_str(content).with_context(|| format!("Failed to parse {name}"))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn remote() -> PackwizSource {
+        PackwizSource::Remote("https://packs.example.com/pack/pack.toml".to_string())
+    }
+
+    #[tokio::test]
+    async fn reads_local_files_below_pack_dir() {
+        let dir = tempfile::tempdir().unwrap();
+        std::fs::create_dir(dir.path().join("mods")).unwrap();
+        std::fs::write(dir.path().join("mods/sodium.pw.toml"), "name").unwrap();
+        let source = PackwizSource::Local(dir.path().to_path_buf());
+
+        assert_eq!(source.read("mods/sodium.pw.toml").await.unwrap(), b"name");
+        assert_eq!(source.read("./mods/sodium.pw.toml").await.unwrap(), b"name");
+    }
+
+    #[tokio::test]
+    async fn rejects_parent_directories() {
+        let dir = tempfile::tempdir().unwrap();
+        let source = PackwizSource::Local(dir.path().join("pack"));
+
+        for path in ["../secret.toml", "mods/../../secret.toml"] {
+            assert!(source.read(path).await.is_err(), "{path}");
+            assert!(remote().read(path).await.is_err(), "{path}");
+        }
+    }
+
+    #[tokio::test]
+    async fn rejects_absolute_paths() {
+        let dir = tempfile::tempdir().unwrap();
+        let source = PackwizSource::Local(dir.path().to_path_buf());
+
+        for path in ["/etc/passwd", "//evil.example.com/pack.toml"] {
+            assert!(source.read(path).await.is_err(), "{path}");
+            assert!(remote().read(path).await.is_err(), "{path}");
+        }
+    }
+
+    #[tokio::test]
+    async fn rejects_urls_on_other_hosts() {
+        for path in [
+            "https://evil.example.com/pack/index.toml",
+            "http:evil.example.com/index.toml",
+            "\\\\evil.example.com\\index.toml",
+        ] {
+            let error = remote().read(path).await.unwrap_err();
+            assert!(
+                error.to_string().contains("outside of"),
+                "{path}: {error:#}"
+            );
+        }
+    }
+}
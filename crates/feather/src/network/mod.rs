@@ -1,17 +1,64 @@
-use anyhow::Result;
-use reqwest::blocking;
-use reqwest::header::{HeaderMap, HeaderValue};
+use anyhow::{Context, Result, anyhow};
+use feather_http::{is_transient, retry};
+use futures_util::{StreamExt, stream};
+use indicatif::{MultiProgress, ProgressBar, ProgressStyle};
+use reqwest::header::{ACCEPT_ENCODING, RANGE};
+use reqwest::{Client, RequestBuilder, Response, StatusCode};
 use rustc_hash::FxHashMap;
 use serde::Deserialize;
-use versions::Version;
+use std::fmt::Write;
+use std::io::SeekFrom;
+use std::path::{Path, PathBuf};
+use std::sync::LazyLock;
+use std::time::Duration;
+use tokio::{
+    fs::File,
+    io::{AsyncReadExt, AsyncSeekExt, AsyncWriteExt},
+};
+use versions::Versioning;
+
+use crate::{cache::Cache, checksum::Checksum, cli::printer::Printer};
+
+/// Identifies feather to the APIs and CDNs it talks to.
+pub const USER_AGENT: &str = concat!(
+    "feather/",
+    env!("CARGO_PKG_VERSION"),
+    " (+https://github.com/divaltor/feather)"
+);
+
+const CONNECT_TIMEOUT: Duration = Duration::from_secs(10);
+/// Applies between reads rather than to the whole body, so large jars can take their time.
+const READ_TIMEOUT: Duration = Duration::from_secs(30);
+
+static HTTP_CLIENT: LazyLock<Client> = LazyLock::new(|| {
+    Client::builder()
+        .user_agent(USER_AGENT)
+        .connect_timeout(CONNECT_TIMEOUT)
+        .read_timeout(READ_TIMEOUT)
+        .build()
+        .expect("Failed to build HTTP client")
+});
+
+/// The shared HTTP client, cloning it is cheap and reuses its connection pool.
+pub fn client() -> Client {
+    HTTP_CLIENT.clone()
+}
+
+/// Sends the request built by `request` on the shared client, see [`feather_http::send`].
+pub async fn send<F>(request: F) -> Result<Response>
+where
+    F: Fn(&Client) -> RequestBuilder,
+{
+    feather_http::send(&HTTP_CLIENT, request).await
+}
 
 #[derive(Deserialize)]
 struct VersionManifest {
     versions: Vec<MinecraftVersion>,
 }
 
-#[derive(Deserialize)]
-enum VersionType {
+#[derive(Deserialize, Debug, Clone, Copy, PartialEq, Eq)]
+pub enum VersionType {
     #[serde(rename = "release")]
     Release,
     #[serde(rename = "snapshot")]
@@ -24,37 +71,690 @@ enum VersionType {
 
 #[derive(Deserialize)]
 pub struct MinecraftVersion {
-    id: Version,
+    #[serde(deserialize_with = "Versioning::deserialize_pretty")]
+    pub id: Versioning,
     #[serde(rename = "type")]
-    version_type: VersionType,
+    pub version_type: VersionType,
     url: String,
     sha1: String,
 }
 
-pub struct MinecraftVersions(FxHashMap<Version, MinecraftVersion>);
+#[derive(Deserialize, Debug)]
+pub struct VersionDownload {
+    pub sha1: String,
+    pub size: u64,
+    pub url: String,
+}
 
-impl From<VersionManifest> for MinecraftVersions {
-    fn from(manifest: VersionManifest) -> Self {
-        // PERF: Optimize cloning
-        Self(manifest.versions.into_iter().map(|v| (v.id.clone(), v)).collect())
-    }
+#[derive(Deserialize, Debug)]
+pub struct VersionDownloads {
+    pub server: Option<VersionDownload>,
+}
+
+#[derive(Deserialize, Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct VersionJava {
+    pub major_version: u32,
+}
+
+#[derive(Deserialize, Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct VersionDetails {
+    pub downloads: VersionDownloads,
+    /// Missing for versions older than 1.7.
+    pub java_version: Option<VersionJava>,
+}
+
+pub struct MinecraftVersions {
+    versions: FxHashMap<Versioning, MinecraftVersion>,
 }
 
 impl MinecraftVersions {
-    pub fn new() -> Result<Self> {
+    pub async fn new() -> Result<Self> {
         let url = "https://launchermeta.mojang.com/mc/game/version_manifest_v2.json";
 
-        let mut headers = HeaderMap::new();
-        headers.insert("Accept-Encoding", HeaderValue::from_static("zstd"));
+        tracing::debug!("Fetching Minecraft version manifest from {}", url);
+
+        let response = send(|client| client.get(url).header(ACCEPT_ENCODING, "zstd"))
+            .await
+            .with_context(|| format!("Failed to fetch version manifest from {url}"))?;
+        let body = response
+            .json::<VersionManifest>()
+            .await
+            .context("Failed to parse version manifest")?;
+
+        // PERF: Optimize cloning
+        let versions = body
+            .versions
+            .into_iter()
+            .map(|v| (v.id.clone(), v))
+            .collect();
+
+        Ok(Self { versions })
+    }
+
+    pub fn get_version(&self, id: &Versioning) -> Option<&MinecraftVersion> {
+        self.versions.get(id)
+    }
+
+    /// Fetches the per-version JSON and checks it against the sha1 listed in the manifest.
+    pub async fn get_details(&self, version: &MinecraftVersion) -> Result<VersionDetails> {
+        tracing::debug!(
+            "Fetching version details for {} from {}",
+            version.id,
+            version.url
+        );
+
+        let response = send(|client| client.get(&version.url))
+            .await
+            .with_context(|| format!("Failed to fetch version details from {}", version.url))?;
+        let body = response
+            .bytes()
+            .await
+            .context("Failed to read version details response")?;
+
+        Checksum::Sha1(version.sha1.clone())
+            .verify(&body)
+            .with_context(|| format!("Version details for {} failed verification", version.id))?;
+
+        serde_json::from_slice(&body)
+            .with_context(|| format!("Failed to parse version details for {}", version.id))
+    }
+}
+
+/// Downloads `url` to `path`, refusing it unless it matches the expected checksum and size.
+pub async fn download_file(
+    url: &str,
+    checksum: Option<&Checksum>,
+    size: Option<u64>,
+    path: &Path,
+) -> Result<()> {
+    fetch_to_file(url, checksum, size, path, &ProgressBar::hidden()).await
+}
+
+/// [`stream_to_file`] with retries, a transfer that breaks off midway starts over.
+async fn fetch_to_file(
+    url: &str,
+    checksum: Option<&Checksum>,
+    size: Option<u64>,
+    path: &Path,
+    progress: &ProgressBar,
+) -> Result<()> {
+    retry(|| {
+        progress.reset();
+        stream_to_file(url, checksum, size, path, progress)
+    })
+    .await
+}
+
+/// Streams `url` into a `.part` file next to `path` and only moves it into place once the
+/// size and checksum match, so a truncated or tampered response never replaces `path`.
+///
+/// A `.part` file left behind by an interrupted transfer is resumed with a `Range` request.
+/// Servers that ignore the range get a fresh download instead.
+async fn stream_to_file(
+    url: &str,
+    checksum: Option<&Checksum>,
+    size: Option<u64>,
+    path: &Path,
+    progress: &ProgressBar,
+) -> Result<()> {
+    let part_path = part_path(path);
+    let mut offset = tokio::fs::metadata(&part_path)
+        .await
+        .map_or(0, |metadata| metadata.len());
+
+    // A complete or stale `.part` file cannot be resumed, start over.
+    if size.is_some_and(|size| offset >= size) {
+        offset = 0;
+    }
+
+    tracing::debug!(
+        "Downloading {} to {} from byte {}",
+        url,
+        path.display(),
+        offset
+    );
+
+    let mut request = HTTP_CLIENT.get(url);
+    if offset > 0 {
+        request = request.header(RANGE, format!("bytes={offset}-"));
+    }
+
+    let response = request
+        .send()
+        .await
+        .with_context(|| format!("Failed to download {url}"))?;
+
+    let response = if response.status() == StatusCode::RANGE_NOT_SATISFIABLE {
+        tracing::debug!("{} rejected the range request, restarting", url);
+        offset = 0;
+        HTTP_CLIENT.get(url).send().await
+    } else {
+        Ok(response)
+    }
+    .and_then(Response::error_for_status)
+    .with_context(|| format!("Failed to download {url}"))?;
+
+    if offset > 0 && response.status() != StatusCode::PARTIAL_CONTENT {
+        tracing::debug!("{} ignored the range request, restarting", url);
+        offset = 0;
+    }
+
+    if let Some(length) = size.or(response.content_length().map(|length| length + offset)) {
+        progress.set_length(length);
+    }
+
+    let result = write_response(response, url, checksum, size, &part_path, offset, progress).await;
+
+    // Interrupted transfers keep their `.part` file so the next attempt can resume it.
+    if let Err(error) = result {
+        if !is_transient(&error) {
+            let _ = tokio::fs::remove_file(&part_path).await;
+        }
+        return Err(error);
+    }
+
+    tokio::fs::rename(&part_path, path)
+        .await
+        .with_context(|| format!("Failed to move download into {}", path.display()))
+}
+
+/// Appends the body to `part_path` after its first `offset` bytes, hashing the whole file.
+async fn write_response(
+    mut response: Response,
+    url: &str,
+    checksum: Option<&Checksum>,
+    size: Option<u64>,
+    part_path: &Path,
+    offset: u64,
+    progress: &ProgressBar,
+) -> Result<()> {
+    let mut hasher = checksum.map(Checksum::hasher);
+
+    let mut file = if offset > 0 {
+        let mut file = File::options()
+            .read(true)
+            .write(true)
+            .open(part_path)
+            .await
+            .with_context(|| format!("Failed to open {}", part_path.display()))?;
+
+        // Anything past `offset` was written after the size was read and is discarded.
+        file.set_len(offset).await?;
+
+        if let Some(hasher) = hasher.as_mut() {
+            let mut buffer = vec![0; 64 * 1024];
+            loop {
+                let read = file.read(&mut buffer).await?;
+                if read == 0 {
+                    break;
+                }
+                hasher.update(&buffer[..read]);
+            }
+        }
+
+        file.seek(SeekFrom::Start(offset)).await?;
+        file
+    } else {
+        File::create(part_path)
+            .await
+            .with_context(|| format!("Failed to create {}", part_path.display()))?
+    };
+
+    let mut written = offset;
+    progress.set_position(offset);
+
+    while let Some(chunk) = response
+        .chunk()
+        .await
+        .with_context(|| format!("Failed to read response from {url}"))?
+    {
+        written += chunk.len() as u64;
+
+        // Stop early instead of writing an oversized body to disk.
+        if let Some(size) = size
+            && written > size
+        {
+            return Err(anyhow!(
+                "Download from {} is larger than the expected {} bytes",
+                url,
+                size
+            ));
+        }
+
+        if let Some(hasher) = hasher.as_mut() {
+            hasher.update(&chunk);
+        }
+        file.write_all(&chunk)
+            .await
+            .with_context(|| format!("Failed to write {}", part_path.display()))?;
+        progress.inc(chunk.len() as u64);
+    }
+
+    file.flush()
+        .await
+        .with_context(|| format!("Failed to write {}", part_path.display()))?;
+
+    if let Some(size) = size
+        && written != size
+    {
+        return Err(anyhow!(
+            "Download from {} has unexpected size: expected {} bytes, got {}",
+            url,
+            size,
+            written
+        ));
+    }
+
+    if let (Some(checksum), Some(hasher)) = (checksum, hasher) {
+        checksum
+            .verify_digest(&hasher.finish())
+            .with_context(|| format!("Download from {url} failed verification"))?;
+    }
+
+    Ok(())
+}
+
+fn part_path(path: &Path) -> PathBuf {
+    let mut file_name = path.file_name().unwrap_or_default().to_os_string();
+    file_name.push(".part");
+    path.with_file_name(file_name)
+}
+
+/// A file to fetch, mirrors in `urls` are tried in order until one passes verification.
+#[derive(Debug, Clone)]
+pub struct Download {
+    pub name: String,
+    pub urls: Vec<String>,
+    pub checksum: Option<Checksum>,
+    pub size: Option<u64>,
+    pub path: PathBuf,
+}
+
+/// Fetches batches of files with bounded parallelism, drawing per-file and overall progress.
+#[derive(Clone)]
+pub struct Downloader {
+    concurrency: usize,
+    printer: Printer,
+    cache: Cache,
+}
+
+impl Downloader {
+    pub fn new(concurrency: usize, printer: Printer, cache: Cache) -> Self {
+        Self {
+            concurrency: concurrency.max(1),
+            printer,
+            cache,
+        }
+    }
+
+    /// Downloads every file and fails with the list of files that no mirror could provide.
+    pub async fn download_all(&self, downloads: &[Download]) -> Result<()> {
+        if downloads.is_empty() {
+            return Ok(());
+        }
+
+        let progress = MultiProgress::with_draw_target(self.printer.target());
+        let overall = progress.add(
+            ProgressBar::new(downloads.len() as u64)
+                .with_style(
+                    ProgressStyle::with_template("{msg} [{bar:40}] {pos}/{len} files ({elapsed})")?
+                        .progress_chars("=> "),
+                )
+                .with_message("Downloading"),
+        );
+
+        // Downloads with the same checksum share a cache entry and its `.part` file, so each
+        // entry is fetched once and linked into the other destinations afterwards.
+        let mut primaries = FxHashMap::default();
+        let mut unique = Vec::new();
+        let mut duplicates = Vec::new();
+        for download in downloads {
+            match download
+                .checksum
+                .as_ref()
+                .and_then(|checksum| self.cache.entry_path(checksum))
+            {
+                Some(entry) if primaries.contains_key(&entry) => duplicates.push((download, entry)),
+                Some(entry) => {
+                    primaries.insert(entry, download);
+                    unique.push(download);
+                }
+                None => unique.push(download),
+            }
+        }
+
+        let results = stream::iter(unique)
+            .map(|download| {
+                let progress = &progress;
+                let overall = &overall;
+                async move {
+                    let result = self.download(download, progress).await;
+                    overall.inc(1);
+                    (download, result)
+                }
+            })
+            .buffer_unordered(self.concurrency)
+            .collect::<Vec<_>>()
+            .await;
+
+        let mut cached = 0;
+        let mut failed = Vec::new();
+        for (download, result) in results {
+            match result {
+                Ok(true) => cached += 1,
+                Ok(false) => {}
+                Err(error) => failed.push((download, error)),
+            }
+        }
+
+        for (download, entry) in duplicates {
+            let primary = primaries[&entry];
+
+            if failed
+                .iter()
+                .any(|(failed, _)| std::ptr::eq(*failed, primary))
+            {
+                failed.push((download, anyhow!("{} failed", primary.name)));
+            } else {
+                match self.cache.link(&entry, &download.path).await {
+                    Ok(()) => cached += 1,
+                    Err(error) => failed.push((download, error)),
+                }
+            }
+            overall.inc(1);
+        }
+
+        overall.finish_and_clear();
+
+        if !failed.is_empty() {
+            let mut stderr = self.printer.stderr();
+            for (download, error) in &failed {
+                let _ = stderr.write_str(&format!("{}: {:#}", download.name, error));
+            }
+
+            return Err(anyhow!(
+                "{} files could not be downloaded and verified: {}",
+                failed.len(),
+                failed
+                    .iter()
+                    .map(|(download, _)| download.name.as_str())
+                    .collect::<Vec<_>>()
+                    .join(", ")
+            ));
+        }
+
+        let _ = self.printer.stdout().write_str(&format!(
+            "Installed {} files, {} from the cache",
+            downloads.len(),
+            cached
+        ));
+
+        Ok(())
+    }
+
+    /// Returns whether the file was served from the cache.
+    async fn download(&self, download: &Download, progress: &MultiProgress) -> Result<bool> {
+        let entry = download
+            .checksum
+            .as_ref()
+            .and_then(|checksum| self.cache.entry_path(checksum));
+
+        if let Some(entry) = &entry {
+            if entry.is_file() {
+                tracing::debug!("Using cached {}", download.name);
+                self.cache.link(entry, &download.path).await?;
+                return Ok(true);
+            }
+
+            if let Some(parent) = entry.parent() {
+                tokio::fs::create_dir_all(parent)
+                    .await
+                    .with_context(|| format!("Failed to create {}", parent.display()))?;
+            }
+        }
+
+        // Cacheable files are fetched into the cache and linked into place afterwards.
+        let fetch_path = entry.as_deref().unwrap_or(&download.path);
+
+        let bar = progress.add(
+            ProgressBar::new(download.size.unwrap_or(0))
+                .with_style(
+                    ProgressStyle::with_template(
+                        "  {msg:30!} [{bar:30}] {bytes}/{total_bytes} {bytes_per_sec}",
+                    )?
+                    .progress_chars("=> "),
+                )
+                .with_message(download.name.clone()),
+        );
+
+        let mut last_error = None;
+
+        for url in &download.urls {
+            match fetch_to_file(
+                url,
+                download.checksum.as_ref(),
+                download.size,
+                fetch_path,
+                &bar,
+            )
+            .await
+            {
+                Ok(()) => {
+                    bar.finish_and_clear();
+                    tracing::debug!("Downloaded {}", download.name);
+
+                    if let Some(entry) = &entry {
+                        self.cache.link(entry, &download.path).await?;
+                    }
+                    return Ok(false);
+                }
+                Err(error) => {
+                    progress.suspend(|| tracing::warn!("{:#}", error));
+                    last_error = Some(error);
+                }
+            }
+        }
+
+        bar.finish_and_clear();
+        Err(last_error.unwrap_or_else(|| anyhow!("No download URLs")))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::os::unix::fs::MetadataExt;
+    use std::sync::{Arc, Mutex};
+    use tokio::{io::AsyncBufReadExt, net::TcpListener};
+
+    const BODY: &[u8] = b"feather resumes interrupted downloads from the .part file";
+
+    type Ranges = Arc<Mutex<Vec<Option<String>>>>;
 
-        let client = blocking::Client::builder().default_headers(headers).build()?;
-        let response = client.get(url).send()?;
-        let body = response.json::<VersionManifest>()?;
+    /// Serves `BODY` on a local port and records the `Range` header of every request. Ranges
+    /// are only honoured when `ranges` is set, like a server without range support otherwise.
+    async fn serve(ranges: bool) -> (String, Ranges) {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let url = format!("http://{}/server.jar", listener.local_addr().unwrap());
+        let requests = Ranges::default();
 
-        Ok(MinecraftVersions::from(body))
+        let recorded = requests.clone();
+        tokio::spawn(async move {
+            loop {
+                let (stream, _) = listener.accept().await.unwrap();
+                let (reader, mut writer) = stream.into_split();
+                let mut reader = tokio::io::BufReader::new(reader);
+
+                let mut range = None;
+                let mut line = String::new();
+                while reader.read_line(&mut line).await.unwrap() > 0 && line != "\r\n" {
+                    if let Some((name, value)) = line.split_once(':')
+                        && name.eq_ignore_ascii_case("range")
+                    {
+                        range = Some(value.trim().to_string());
+                    }
+                    line.clear();
+                }
+                recorded.lock().unwrap().push(range.clone());
+
+                let start = range.filter(|_| ranges).and_then(|range| {
+                    range
+                        .strip_prefix("bytes=")?
+                        .strip_suffix('-')?
+                        .parse()
+                        .ok()
+                });
+                let head = match start {
+                    Some(start) => format!(
+                        "HTTP/1.1 206 Partial Content\r\nContent-Range: bytes {}-{}/{}\r\n",
+                        start,
+                        BODY.len() - 1,
+                        BODY.len()
+                    ),
+                    None => "HTTP/1.1 200 OK\r\n".to_string(),
+                };
+                let body = &BODY[start.unwrap_or(0)..];
+
+                writer
+                    .write_all(
+                        format!(
+                            "{head}Content-Length: {}\r\nConnection: close\r\n\r\n",
+                            body.len()
+                        )
+                        .as_bytes(),
+                    )
+                    .await
+                    .unwrap();
+                writer.write_all(body).await.unwrap();
+            }
+        });
+
+        (url, requests)
+    }
+
+    fn sha256(bytes: &[u8]) -> Checksum {
+        Checksum::Sha256(Checksum::Sha256(String::new()).digest(bytes))
+    }
+
+    fn size() -> Option<u64> {
+        Some(BODY.len() as u64)
+    }
+
+    #[tokio::test]
+    async fn resumes_part_file_with_range_request() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("server.jar");
+        std::fs::write(part_path(&path), &BODY[..10]).unwrap();
+        let (url, requests) = serve(true).await;
+
+        download_file(&url, Some(&sha256(BODY)), size(), &path)
+            .await
+            .unwrap();
+
+        assert_eq!(std::fs::read(&path).unwrap(), BODY);
+        assert!(!part_path(&path).exists());
+        assert_eq!(*requests.lock().unwrap(), [Some("bytes=10-".to_string())]);
     }
-    
-    pub fn get_version(&self, id: &Version) -> Option<&MinecraftVersion> {
-        self.0.get(id)
+
+    #[tokio::test]
+    async fn restarts_when_server_ignores_range() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("server.jar");
+        std::fs::write(part_path(&path), &BODY[..10]).unwrap();
+        let (url, requests) = serve(false).await;
+
+        download_file(&url, Some(&sha256(BODY)), size(), &path)
+            .await
+            .unwrap();
+
+        assert_eq!(std::fs::read(&path).unwrap(), BODY);
+        assert_eq!(*requests.lock().unwrap(), [Some("bytes=10-".to_string())]);
     }
-}
\ No newline at end of file
+
+    #[tokio::test]
+    async fn restarts_when_part_file_is_complete() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("server.jar");
+        std::fs::write(part_path(&path), BODY).unwrap();
+        let (url, requests) = serve(true).await;
+
+        download_file(&url, Some(&sha256(BODY)), size(), &path)
+            .await
+            .unwrap();
+
+        assert_eq!(std::fs::read(&path).unwrap(), BODY);
+        assert_eq!(*requests.lock().unwrap(), [None]);
+    }
+
+    #[tokio::test]
+    async fn rejects_unexpected_size() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("server.jar");
+        let (url, _) = serve(true).await;
+
+        let error = download_file(&url, None, Some(BODY.len() as u64 + 1), &path)
+            .await
+            .unwrap_err();
+
+        assert!(format!("{error:#}").contains("unexpected size"));
+        assert!(!path.exists());
+        assert!(!part_path(&path).exists());
+    }
+
+    #[tokio::test]
+    async fn discards_tampered_part_file_after_resume() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("server.jar");
+        std::fs::write(part_path(&path), b"tampered!!").unwrap();
+        let (url, requests) = serve(true).await;
+
+        let error = download_file(&url, Some(&sha256(BODY)), size(), &path)
+            .await
+            .unwrap_err();
+
+        assert!(format!("{error:#}").contains("sha256 mismatch"));
+        assert!(!path.exists());
+        assert!(!part_path(&path).exists());
+
+        // The next attempt starts over instead of resuming the bad bytes.
+        download_file(&url, Some(&sha256(BODY)), size(), &path)
+            .await
+            .unwrap();
+
+        assert_eq!(std::fs::read(&path).unwrap(), BODY);
+        assert_eq!(
+            *requests.lock().unwrap(),
+            [Some("bytes=10-".to_string()), None]
+        );
+    }
+
+    #[tokio::test]
+    async fn fetches_shared_checksum_once() {
+        let dir = tempfile::tempdir().unwrap();
+        let cache = Cache::new(&dir.path().join("cache"));
+        let (url, requests) = serve(true).await;
+
+        let downloads = ["a.jar", "b.jar"].map(|name| Download {
+            name: name.to_string(),
+            urls: vec![url.clone()],
+            checksum: Some(sha256(BODY)),
+            size: size(),
+            path: dir.path().join(name),
+        });
+        Downloader::new(2, Printer::Silent, cache.clone())
+            .download_all(&downloads)
+            .await
+            .unwrap();
+
+        assert_eq!(requests.lock().unwrap().len(), 1);
+
+        let entry = cache.entry_path(&sha256(BODY)).unwrap();
+        let inode = std::fs::metadata(&entry).unwrap().ino();
+        for download in &downloads {
+            assert_eq!(std::fs::read(&download.path).unwrap(), BODY);
+            assert_eq!(std::fs::metadata(&download.path).unwrap().ino(), inode);
+        }
+    }
+}
@@ -0,0 +1,615 @@
+use crate::{
+    checksum::Checksum,
+    cli::ProxyArgs,
+    config::{ConfigGenerator, random_secret, set_yaml, update_yaml},
+    installer::{chown_to_feather, create_feather_user},
+    instance::Registry,
+    java::{JavaInstaller, JavaVersion},
+    minecraft::ServerLaunch,
+    network, systemd,
+};
+use anyhow::{Context, Result, anyhow};
+use feather_paper::{PaperClient, Project};
+use serde::Deserialize;
+use serde_yaml::{Mapping, Value};
+use std::{
+    collections::BTreeSet,
+    fmt,
+    io::Write,
+    os::unix::fs::OpenOptionsExt,
+    path::{Path, PathBuf},
+    str::FromStr,
+};
+use versions::Versioning;
+
+const PROXY_JAR: &str = "proxy.jar";
+const FIRST_BACKEND_PORT: u16 = 25566;
+
+const BUNGEECORD_JOB_URL: &str = "https://ci.md-5.net/job/BungeeCord";
+const BUNGEECORD_ARTIFACT: &str = "bootstrap/target/BungeeCord.jar";
+
+/// A Jenkins build with the fingerprints of its artifacts.
+#[derive(Deserialize)]
+struct JenkinsBuild {
+    number: u32,
+    #[serde(default)]
+    fingerprint: Vec<JenkinsFingerprint>,
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct JenkinsFingerprint {
+    file_name: String,
+    /// MD5 of the artifact.
+    hash: String,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ProxyKind {
+    Velocity,
+    Waterfall,
+    BungeeCord,
+}
+
+impl fmt::Display for ProxyKind {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{}",
+            match self {
+                ProxyKind::Velocity => "velocity",
+                ProxyKind::Waterfall => "waterfall",
+                ProxyKind::BungeeCord => "bungeecord",
+            }
+        )
+    }
+}
+
+impl FromStr for ProxyKind {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        Ok(match s {
+            "velocity" => ProxyKind::Velocity,
+            "waterfall" => ProxyKind::Waterfall,
+            "bungeecord" | "bungee" => ProxyKind::BungeeCord,
+            _ => return Err(anyhow!("Unknown proxy: {}", s)),
+        })
+    }
+}
+
+/// How the proxy hands player information to its backends.
+pub enum Forwarding {
+    /// Velocity's modern forwarding, authenticated with a shared secret.
+    Velocity { secret: String },
+    /// Legacy BungeeCord IP forwarding.
+    BungeeCord,
+}
+
+/// A feather-managed server sitting behind the proxy.
+struct Backend {
+    name: String,
+    dir: PathBuf,
+    port: u16,
+}
+
+impl Backend {
+    fn is_plugin_server(&self) -> bool {
+        self.dir.join("plugins").is_dir()
+    }
+}
+
+pub struct ProxyInstaller {
+    kind: ProxyKind,
+    version: Option<Versioning>,
+    port: u16,
+    java_cache_dir: PathBuf,
+    proxy_dir: PathBuf,
+    minecraft_servers_dir: PathBuf,
+    instances_file: PathBuf,
+    java_args: Vec<String>,
+}
+
+impl ProxyInstaller {
+    pub fn new(
+        args: &ProxyArgs,
+        java_cache_dir: &Path,
+        proxies_dir: &Path,
+        minecraft_servers_dir: &Path,
+        instances_file: &Path,
+    ) -> Self {
+        Self {
+            kind: args.kind,
+            version: args.version.clone(),
+            port: args.port,
+            java_cache_dir: java_cache_dir.to_path_buf(),
+            proxy_dir: proxies_dir.join(args.kind.to_string()),
+            minecraft_servers_dir: minecraft_servers_dir.to_path_buf(),
+            instances_file: instances_file.to_path_buf(),
+            java_args: args.java_args.clone(),
+        }
+    }
+
+    pub async fn install(&self) -> Result<()> {
+        tracing::info!("Starting {} proxy installation...", self.kind);
+
+        std::fs::create_dir_all(&self.proxy_dir).with_context(|| {
+            format!(
+                "Failed to create proxy directory: {}",
+                self.proxy_dir.display()
+            )
+        })?;
+
+        create_feather_user().context("Failed to create feather user")?;
+
+        // Current Velocity and BungeeCord builds both require Java 21.
+        let java_executable = JavaInstaller::new(&self.java_cache_dir)
+            .install(JavaVersion(21))
+            .await
+            .context("Failed to install Java")?;
+
+        self.install_proxy_jar()
+            .await
+            .with_context(|| format!("Failed to install {}", self.kind))?;
+
+        let backends = self
+            .discover_backends()
+            .context("Failed to discover backend servers")?;
+
+        if backends.is_empty() {
+            tracing::warn!(
+                "No servers found in {}, the proxy has nothing to forward to",
+                self.minecraft_servers_dir.display()
+            );
+        }
+
+        let forwarding = match self.kind {
+            ProxyKind::Velocity => {
+                let secret = self.forwarding_secret()?;
+                self.write_velocity_config(&backends)?;
+                Forwarding::Velocity { secret }
+            }
+            ProxyKind::Waterfall | ProxyKind::BungeeCord => {
+                self.write_bungee_config(&backends)?;
+                Forwarding::BungeeCord
+            }
+        };
+
+        self.configure_backends(&backends, &forwarding)
+            .context("Failed to configure backend servers")?;
+
+        ConfigGenerator::new(&self.proxy_dir).create_feather_env_file(
+            &java_executable,
+            &self.java_args,
+            &ServerLaunch::Jar(PROXY_JAR.into()),
+        )?;
+
+        // The proxy runs as feather, which must own its config and the forwarding secret.
+        chown_to_feather(&self.proxy_dir).context("Failed to hand the proxy to feather")?;
+
+        if systemd::is_running() {
+            self.setup_systemd()
+                .context("Failed to setup systemd service")?;
+        } else {
+            tracing::warn!(
+                "systemd is not running, skipping the service. Start the proxy from {} with the command in feather.env.",
+                self.proxy_dir.display()
+            );
+        }
+
+        tracing::info!("{} proxy installation completed successfully", self.kind);
+        Ok(())
+    }
+
+    async fn install_proxy_jar(&self) -> Result<()> {
+        let jar_path = self.proxy_dir.join(PROXY_JAR);
+
+        let project = match self.kind {
+            ProxyKind::Velocity => Project::Velocity,
+            ProxyKind::Waterfall => Project::Waterfall,
+            ProxyKind::BungeeCord => return install_bungeecord(&jar_path).await,
+        };
+
+        let client = PaperClient::with_client(network::client());
+        let version = match &self.version {
+            Some(version) => version.clone(),
+            None => client.get_latest_version(project).await?,
+        };
+
+        let builds = client.get_builds(project, &version).await?;
+        let build = builds
+            .iter()
+            .find(|build| build.is_stable())
+            .or_else(|| builds.first())
+            .ok_or_else(|| anyhow!("{} {} has no builds", project, version))?;
+        let download = build
+            .server_download()
+            .ok_or_else(|| anyhow!("{} build {} has no download", project, build.id))?;
+
+        tracing::info!("Installing {} {} build {}", project, version, build.id);
+
+        network::download_file(
+            &download.url,
+            Some(&Checksum::Sha256(download.checksums.sha256.clone())),
+            Some(download.size),
+            &jar_path,
+        )
+        .await
+    }
+
+    /// Finds feather servers and gives each a port distinct from the proxy and each other,
+    /// keeping the ports servers already use where possible.
+    fn discover_backends(&self) -> Result<Vec<Backend>> {
+        if !self.minecraft_servers_dir.exists() {
+            return Ok(Vec::new());
+        }
+
+        let mut dirs = std::fs::read_dir(&self.minecraft_servers_dir)?
+            .filter_map(|entry| entry.ok())
+            .map(|entry| entry.path())
+            .filter(|path| path.join("feather.env").exists())
+            .collect::<Vec<_>>();
+        dirs.sort();
+
+        let mut used = BTreeSet::from([self.port]);
+        for dir in &dirs {
+            if let Some((rcon_port, _)) = ConfigGenerator::new(dir).read_rcon()? {
+                used.insert(rcon_port);
+            }
+        }
+
+        let mut current_ports = Vec::with_capacity(dirs.len());
+
+        for dir in &dirs {
+            let port = ConfigGenerator::new(dir)
+                .read_server_property("server-port")?
+                .and_then(|port| port.parse::<u16>().ok())
+                .filter(|port| used.insert(*port));
+            current_ports.push(port);
+        }
+
+        let mut next_port = FIRST_BACKEND_PORT;
+        let mut backends = Vec::with_capacity(dirs.len());
+
+        for (dir, port) in dirs.into_iter().zip(current_ports) {
+            let port = match port {
+                Some(port) => port,
+                None => {
+                    while used.contains(&next_port) {
+                        next_port += 1;
+                    }
+                    used.insert(next_port);
+                    next_port
+                }
+            };
+
+            let name = dir
+                .file_name()
+                .map(|name| name.to_string_lossy().into_owned())
+                .with_context(|| format!("Invalid server directory: {}", dir.display()))?;
+
+            backends.push(Backend { name, dir, port });
+        }
+
+        Ok(backends)
+    }
+
+    /// Reuses the existing secret so already configured backends stay valid.
+    fn forwarding_secret(&self) -> Result<String> {
+        let secret_path = self.proxy_dir.join("forwarding.secret");
+
+        if secret_path.exists() {
+            let secret = std::fs::read_to_string(&secret_path)
+                .with_context(|| format!("Failed to read {}", secret_path.display()))?;
+            return Ok(secret.trim().to_string());
+        }
+
+        let secret = random_secret().context("Failed to generate forwarding secret")?;
+
+        // Anyone holding the secret can log in to the backends as any player.
+        std::fs::OpenOptions::new()
+            .write(true)
+            .create_new(true)
+            .mode(0o600)
+            .open(&secret_path)
+            .and_then(|mut file| file.write_all(secret.as_bytes()))
+            .with_context(|| format!("Failed to write {}", secret_path.display()))?;
+
+        tracing::info!("Generated forwarding secret at {}", secret_path.display());
+        Ok(secret)
+    }
+
+    fn write_velocity_config(&self, backends: &[Backend]) -> Result<()> {
+        tracing::info!("Writing velocity.toml...");
+
+        let config_path = self.proxy_dir.join("velocity.toml");
+
+        let mut config = if config_path.exists() {
+            std::fs::read_to_string(&config_path)
+                .with_context(|| format!("Failed to read {}", config_path.display()))?
+                .parse::<toml::Table>()
+                .with_context(|| format!("Failed to parse {}", config_path.display()))?
+        } else {
+            toml::Table::new()
+        };
+
+        config
+            .entry("config-version")
+            .or_insert_with(|| "2.7".into());
+        config.insert("bind".into(), format!("0.0.0.0:{}", self.port).into());
+        config.insert("online-mode".into(), true.into());
+        config.insert("player-info-forwarding-mode".into(), "modern".into());
+        config.insert("forwarding-secret-file".into(), "forwarding.secret".into());
+
+        let mut servers = toml::Table::new();
+        for backend in backends {
+            servers.insert(
+                backend.name.clone(),
+                format!("127.0.0.1:{}", backend.port).into(),
+            );
+        }
+        servers.insert(
+            "try".into(),
+            toml::Value::Array(
+                backends
+                    .iter()
+                    .map(|backend| backend.name.clone().into())
+                    .collect(),
+            ),
+        );
+        config.insert("servers".into(), servers.into());
+
+        // Velocity refuses to start when a forced host points at an unknown server.
+        let forced_hosts = config
+            .entry("forced-hosts")
+            .or_insert_with(|| toml::Table::new().into());
+        if let Some(forced_hosts) = forced_hosts.as_table_mut() {
+            forced_hosts.retain(|_, targets| {
+                targets.as_array().is_some_and(|targets| {
+                    targets.iter().all(|target| {
+                        backends
+                            .iter()
+                            .any(|backend| target.as_str() == Some(&backend.name))
+                    })
+                })
+            });
+        }
+
+        std::fs::write(&config_path, toml::to_string(&config)?)
+            .with_context(|| format!("Failed to write {}", config_path.display()))?;
+
+        tracing::debug!("Velocity config written to {}", config_path.display());
+        Ok(())
+    }
+
+    fn write_bungee_config(&self, backends: &[Backend]) -> Result<()> {
+        tracing::info!("Writing config.yml...");
+
+        let config_path = self.proxy_dir.join("config.yml");
+        let host = Value::String(format!("0.0.0.0:{}", self.port));
+        let priorities = Value::Sequence(
+            backends
+                .iter()
+                .map(|backend| Value::String(backend.name.clone()))
+                .collect(),
+        );
+
+        update_yaml(&config_path, |root| {
+            set_yaml(root, &["ip_forward"], Value::Bool(true));
+            set_yaml(root, &["online_mode"], Value::Bool(true));
+
+            let mut servers = Mapping::new();
+            for backend in backends {
+                let mut server = Mapping::new();
+                set_yaml(
+                    &mut server,
+                    &["address"],
+                    Value::String(format!("127.0.0.1:{}", backend.port)),
+                );
+                set_yaml(&mut server, &["motd"], Value::String(backend.name.clone()));
+                set_yaml(&mut server, &["restricted"], Value::Bool(false));
+                servers.insert(Value::String(backend.name.clone()), Value::Mapping(server));
+            }
+            set_yaml(root, &["servers"], Value::Mapping(servers));
+
+            let listeners = root
+                .entry(Value::String("listeners".into()))
+                .or_insert_with(|| Value::Sequence(Vec::new()));
+            if !listeners.is_sequence() {
+                *listeners = Value::Sequence(Vec::new());
+            }
+
+            let listeners = listeners.as_sequence_mut().unwrap();
+            if listeners.is_empty() {
+                listeners.push(Value::Mapping(Mapping::new()));
+            }
+            if let Some(listener) = listeners[0].as_mapping_mut() {
+                set_yaml(listener, &["host"], host);
+                set_yaml(listener, &["priorities"], priorities);
+            }
+        })
+    }
+
+    fn configure_backends(&self, backends: &[Backend], forwarding: &Forwarding) -> Result<()> {
+        let mut registry = Registry::load(&self.instances_file)?;
+
+        for backend in backends {
+            tracing::info!(
+                "Configuring backend {} on port {}...",
+                backend.name,
+                backend.port
+            );
+
+            let config_generator = ConfigGenerator::new(&backend.dir);
+
+            // Backends only listen on loopback so players cannot bypass the proxy.
+            config_generator.set_server_properties(&[
+                ("server-ip", "127.0.0.1".to_string()),
+                ("server-port", backend.port.to_string()),
+                ("online-mode", "false".to_string()),
+            ])?;
+
+            if backend.is_plugin_server() {
+                config_generator.configure_paper_forwarding(forwarding)?;
+            } else {
+                tracing::warn!(
+                    "Backend {} is not a plugin server, install a forwarding mod such as FabricProxy-Lite for it to accept {} connections",
+                    backend.name,
+                    self.kind
+                );
+            }
+
+            // Config files created above belong to root, but the server rewrites them on start.
+            chown_to_feather(&backend.dir)
+                .with_context(|| format!("Failed to hand backend {} to feather", backend.name))?;
+
+            // Keeps `feather list` and the port checks of `feather init` in line with the move.
+            if let Some(instance) = registry.get_mut(&backend.name) {
+                instance.port = backend.port;
+            }
+        }
+
+        registry.save(&self.instances_file)
+    }
+
+    fn setup_systemd(&self) -> Result<()> {
+        tracing::info!("Setting up systemd service...");
+
+        let kind = self.kind.to_string();
+        let unit_name = systemd::proxy_unit_name(&kind);
+        let service_content = systemd::render_proxy_unit(&kind, &self.proxy_dir, &self.java_args);
+
+        systemd::install_unit(&unit_name, &service_content)?;
+
+        tracing::info!("Systemd service {} configured successfully", unit_name);
+        Ok(())
+    }
+}
+
+/// BungeeCord only publishes builds on Jenkins, so the jar is checked against the MD5
+/// fingerprint Jenkins recorded for it. Pinning the build number keeps a build finishing
+/// in between from mixing up the fingerprint and the jar.
+async fn install_bungeecord(jar_path: &Path) -> Result<()> {
+    let build = network::send(|client| {
+        client.get(format!(
+            "{BUNGEECORD_JOB_URL}/lastSuccessfulBuild/api/json?tree=number,fingerprint[fileName,hash]"
+        ))
+    })
+    .await?
+    .json::<JenkinsBuild>()
+    .await
+    .context("Failed to parse the BungeeCord build")?;
+
+    let file_name = Path::new(BUNGEECORD_ARTIFACT)
+        .file_name()
+        .and_then(|name| name.to_str())
+        .unwrap_or_default();
+    let checksum = build
+        .fingerprint
+        .into_iter()
+        .find(|fingerprint| fingerprint.file_name == file_name)
+        .map(|fingerprint| Checksum::Md5(fingerprint.hash.to_ascii_lowercase()))
+        .with_context(|| {
+            format!(
+                "BungeeCord build {} has no fingerprint for {}, refusing to install it unverified",
+                build.number, file_name
+            )
+        })?;
+
+    tracing::info!("Installing BungeeCord build {}", build.number);
+
+    let url = format!(
+        "{BUNGEECORD_JOB_URL}/{}/artifact/{BUNGEECORD_ARTIFACT}",
+        build.number
+    );
+    network::download_file(&url, Some(&checksum), None, jar_path).await
+}
+
+/// Ports the installed proxies listen on, read from their configs.
+pub fn proxy_ports(proxies_dir: &Path) -> Result<BTreeSet<u16>> {
+    let mut ports = BTreeSet::new();
+
+    if !proxies_dir.exists() {
+        return Ok(ports);
+    }
+
+    for entry in std::fs::read_dir(proxies_dir)
+        .with_context(|| format!("Failed to read {}", proxies_dir.display()))?
+    {
+        let dir = entry?.path();
+        let velocity_config = dir.join("velocity.toml");
+        let bungee_config = dir.join("config.yml");
+
+        let binds = if velocity_config.exists() {
+            std::fs::read_to_string(&velocity_config)
+                .with_context(|| format!("Failed to read {}", velocity_config.display()))?
+                .parse::<toml::Table>()
+                .with_context(|| format!("Failed to parse {}", velocity_config.display()))?
+                .get("bind")
+                .and_then(|bind| bind.as_str())
+                .map(str::to_string)
+                .into_iter()
+                .collect()
+        } else if bungee_config.exists() {
+            let content = std::fs::read_to_string(&bungee_config)
+                .with_context(|| format!("Failed to read {}", bungee_config.display()))?;
+            serde_yaml::from_str::<Value>(&content)
+                .with_context(|| format!("Failed to parse {}", bungee_config.display()))?
+                .get("listeners")
+                .and_then(Value::as_sequence)
+                .into_iter()
+                .flatten()
+                .filter_map(|listener| listener.get("host")?.as_str().map(str::to_string))
+                .collect()
+        } else {
+            Vec::new()
+        };
+
+        ports.extend(
+            binds
+                .iter()
+                .filter_map(|bind| bind.rsplit_once(':')?.1.parse::<u16>().ok()),
+        );
+    }
+
+    Ok(ports)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn proxy_ports_reads_every_proxy_config() {
+        let proxies = tempfile::tempdir().unwrap();
+
+        let velocity = proxies.path().join("velocity");
+        std::fs::create_dir(&velocity).unwrap();
+        std::fs::write(velocity.join("velocity.toml"), "bind = \"0.0.0.0:25565\"\n").unwrap();
+
+        let waterfall = proxies.path().join("waterfall");
+        std::fs::create_dir(&waterfall).unwrap();
+        std::fs::write(
+            waterfall.join("config.yml"),
+            "listeners:\n- host: 0.0.0.0:25577\n- host: 127.0.0.1:25578\n",
+        )
+        .unwrap();
+
+        std::fs::create_dir(proxies.path().join("empty")).unwrap();
+
+        assert_eq!(
+            proxy_ports(proxies.path()).unwrap(),
+            BTreeSet::from([25565, 25577, 25578])
+        );
+    }
+
+    #[test]
+    fn proxy_ports_without_proxies() {
+        let proxies = tempfile::tempdir().unwrap();
+        assert!(
+            proxy_ports(&proxies.path().join("missing"))
+                .unwrap()
+                .is_empty()
+        );
+    }
+}
@@ -0,0 +1,160 @@
+use anyhow::{Context, Result, anyhow};
+use tokio::{
+    io::{AsyncReadExt, AsyncWriteExt},
+    net::TcpStream,
+};
+
+const LOGIN: i32 = 3;
+const COMMAND: i32 = 2;
+const AUTH_RESPONSE: i32 = 2;
+const RESPONSE: i32 = 0;
+
+/// Minimal client for the Source RCON protocol Minecraft servers speak.
+pub struct RconClient {
+    stream: TcpStream,
+    next_id: i32,
+}
+
+impl RconClient {
+    pub async fn connect(port: u16, password: &str) -> Result<Self> {
+        let stream = TcpStream::connect(("127.0.0.1", port))
+            .await
+            .with_context(|| format!("Failed to connect to RCON on port {port}"))?;
+
+        let mut client = Self { stream, next_id: 1 };
+        let id = client.send(LOGIN, password).await?;
+
+        loop {
+            let (response_id, kind, _) = client.receive().await?;
+
+            if kind != AUTH_RESPONSE {
+                continue;
+            }
+            if response_id == -1 || response_id != id {
+                return Err(anyhow!("RCON rejected the password"));
+            }
+
+            return Ok(client);
+        }
+    }
+
+    /// Runs a console command and returns its output.
+    pub async fn command(&mut self, command: &str) -> Result<String> {
+        let id = self.send(COMMAND, command).await?;
+
+        loop {
+            let (response_id, kind, body) = self.receive().await?;
+
+            if kind == RESPONSE && response_id == id {
+                return Ok(body);
+            }
+        }
+    }
+
+    async fn send(&mut self, kind: i32, body: &str) -> Result<i32> {
+        let id = self.next_id;
+        self.next_id += 1;
+
+        self.stream
+            .write_all(&encode(id, kind, body))
+            .await
+            .context("Failed to send RCON packet")?;
+
+        Ok(id)
+    }
+
+    async fn receive(&mut self) -> Result<(i32, i32, String)> {
+        let length = self
+            .stream
+            .read_i32_le()
+            .await
+            .context("Failed to read RCON packet")?;
+
+        if !(10..=4096 + 10).contains(&length) {
+            return Err(anyhow!("Invalid RCON packet length {}", length));
+        }
+
+        let mut packet = vec![0; length as usize];
+        self.stream
+            .read_exact(&mut packet)
+            .await
+            .context("Failed to read RCON packet")?;
+
+        decode(&packet)
+    }
+}
+
+/// A packet including its length prefix.
+fn encode(id: i32, kind: i32, body: &str) -> Vec<u8> {
+    // Length covers the id, the type, the body and its two trailing NULs.
+    let length = 4 + 4 + body.len() as i32 + 2;
+
+    let mut packet = Vec::with_capacity(4 + length as usize);
+    packet.extend_from_slice(&length.to_le_bytes());
+    packet.extend_from_slice(&id.to_le_bytes());
+    packet.extend_from_slice(&kind.to_le_bytes());
+    packet.extend_from_slice(body.as_bytes());
+    packet.extend_from_slice(&[0, 0]);
+    packet
+}
+
+/// Id, type and body of a packet read after its length prefix.
+fn decode(packet: &[u8]) -> Result<(i32, i32, String)> {
+    if packet.len() < 10 {
+        return Err(anyhow!("RCON packet too short: {} bytes", packet.len()));
+    }
+
+    let id = i32::from_le_bytes(packet[0..4].try_into()?);
+    let kind = i32::from_le_bytes(packet[4..8].try_into()?);
+    let body = String::from_utf8_lossy(&packet[8..packet.len() - 2]).into_owned();
+
+    Ok((id, kind, body))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn encodes_login() {
+        assert_eq!(
+            encode(1, LOGIN, "pw"),
+            [
+                12, 0, 0, 0, // length
+                1, 0, 0, 0, // id
+                3, 0, 0, 0, // type
+                b'p', b'w', 0, 0,
+            ]
+        );
+    }
+
+    #[test]
+    fn encodes_empty_body() {
+        let packet = encode(7, COMMAND, "");
+        assert_eq!(i32::from_le_bytes(packet[0..4].try_into().unwrap()), 10);
+        assert_eq!(packet.len(), 14);
+    }
+
+    #[test]
+    fn round_trips() {
+        let packet = encode(42, COMMAND, "say héllo");
+        assert_eq!(
+            decode(&packet[4..]).unwrap(),
+            (42, COMMAND, "say héllo".to_string())
+        );
+    }
+
+    #[test]
+    fn decodes_rejected_login() {
+        let packet = encode(-1, AUTH_RESPONSE, "");
+        assert_eq!(
+            decode(&packet[4..]).unwrap(),
+            (-1, AUTH_RESPONSE, String::new())
+        );
+    }
+
+    #[test]
+    fn rejects_truncated_packets() {
+        assert!(decode(&[1, 0, 0, 0, 0, 0]).is_err());
+    }
+}
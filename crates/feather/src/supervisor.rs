@@ -0,0 +1,252 @@
+use anyhow::{Context, Result, anyhow};
+use indicatif::HumanDuration;
+use std::{
+    path::Path,
+    process::{ExitStatus, Stdio},
+    time::{Duration, Instant},
+};
+use tokio::{
+    io::{AsyncBufReadExt, AsyncWriteExt, BufReader, Lines, Stdin},
+    process::{Child, ChildStdin, Command},
+    signal::unix::{Signal, SignalKind, signal},
+};
+
+use crate::{config::ConfigGenerator, installer::feather_ids, instance::Instance};
+
+/// A server that stayed up this long is considered healthy again and resets the crash count.
+const STABLE_AFTER: Duration = Duration::from_secs(60);
+const INITIAL_BACKOFF: Duration = Duration::from_secs(2);
+const MAX_BACKOFF: Duration = Duration::from_secs(300);
+/// Consecutive crashes before a crash loop is given up on.
+const MAX_CRASHES: u32 = 10;
+/// How long the JVM gets to exit after SIGTERM before it is killed.
+const TERM_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// Runs an instance in the foreground, for hosts without systemd.
+///
+/// Console input is forwarded to the server, and SIGTERM or Ctrl-C issue an in-game `stop` so
+/// the worlds are saved. With `restart`, crashes restart the server after an exponential backoff.
+pub struct Supervisor<'a> {
+    instance: &'a Instance,
+    restart: bool,
+    stop_timeout: Duration,
+}
+
+enum Outcome {
+    Exited(ExitStatus),
+    Stopped,
+}
+
+struct Signals {
+    terminate: Signal,
+    interrupt: Signal,
+}
+
+impl Signals {
+    async fn recv(&mut self) {
+        tokio::select! {
+            _ = self.terminate.recv() => {}
+            _ = self.interrupt.recv() => {}
+        }
+    }
+}
+
+impl<'a> Supervisor<'a> {
+    pub fn new(instance: &'a Instance, restart: bool, stop_timeout: Duration) -> Self {
+        Self {
+            instance,
+            restart,
+            stop_timeout,
+        }
+    }
+
+    pub async fn run(&self) -> Result<()> {
+        let mut console = Some(BufReader::new(tokio::io::stdin()).lines());
+        let mut signals = Signals {
+            terminate: signal(SignalKind::terminate()).context("Failed to handle SIGTERM")?,
+            interrupt: signal(SignalKind::interrupt()).context("Failed to handle SIGINT")?,
+        };
+        let mut crashes = 0;
+        // SAFETY: geteuid has no preconditions and cannot fail.
+        let user = if unsafe { libc::geteuid() } == 0 {
+            Some(feather_ids().context("Failed to look up the feather user")?)
+        } else {
+            None
+        };
+
+        loop {
+            // Read on every start so `feather java use` applies to the next restart.
+            let command = launch_command(&self.instance.dir)?;
+
+            tracing::info!("Starting {}", self.instance.name);
+            tracing::debug!("Running {}", command.join(" "));
+
+            let started = Instant::now();
+            let mut server = Command::new(&command[0]);
+            server
+                .args(&command[1..])
+                .current_dir(&self.instance.dir)
+                .stdin(Stdio::piped())
+                // Keeps Ctrl-C in a terminal from reaching the server before it gets `stop`.
+                .process_group(0)
+                .kill_on_drop(true);
+
+            // Like the systemd unit, never run the server as root, which would leave files
+            // behind that feather cannot write.
+            if let Some((uid, gid)) = user {
+                server.uid(uid).gid(gid);
+            }
+
+            let mut child = server
+                .spawn()
+                .with_context(|| format!("Failed to start {}", command[0]))?;
+
+            let status = match self
+                .supervise(&mut child, &mut console, &mut signals)
+                .await?
+            {
+                Outcome::Stopped => return Ok(()),
+                Outcome::Exited(status) => status,
+            };
+
+            if status.success() {
+                tracing::info!("{} stopped", self.instance.name);
+                return Ok(());
+            }
+
+            if !self.restart {
+                return Err(anyhow!("{} exited with {}", self.instance.name, status));
+            }
+
+            if started.elapsed() >= STABLE_AFTER {
+                crashes = 0;
+            }
+            crashes += 1;
+
+            if crashes > MAX_CRASHES {
+                return Err(anyhow!(
+                    "{} crashed {} times in a row, giving up",
+                    self.instance.name,
+                    MAX_CRASHES
+                ));
+            }
+
+            let backoff = INITIAL_BACKOFF
+                .saturating_mul(1 << (crashes - 1).min(16))
+                .min(MAX_BACKOFF);
+
+            tracing::warn!(
+                "{} exited with {}, restarting in {}",
+                self.instance.name,
+                status,
+                HumanDuration(backoff)
+            );
+
+            tokio::select! {
+                _ = tokio::time::sleep(backoff) => {}
+                _ = signals.recv() => return Ok(()),
+            }
+        }
+    }
+
+    async fn supervise(
+        &self,
+        child: &mut Child,
+        console: &mut Option<Lines<BufReader<Stdin>>>,
+        signals: &mut Signals,
+    ) -> Result<Outcome> {
+        let mut stdin = child.stdin.take().context("Server has no stdin")?;
+
+        loop {
+            tokio::select! {
+                status = child.wait() => return Ok(Outcome::Exited(status?)),
+                line = next_line(console) => match line {
+                    Some(line) => {
+                        if let Err(e) = send(&mut stdin, &line).await {
+                            tracing::warn!("Failed to forward console input: {:#}", e);
+                        }
+                    }
+                    // Detached from a terminal, keep running without console input.
+                    None => *console = None,
+                },
+                _ = signals.recv() => {
+                    self.stop(child, &mut stdin).await?;
+                    return Ok(Outcome::Stopped);
+                }
+            }
+        }
+    }
+
+    /// Issues `stop` on the console, then escalates to SIGTERM and finally SIGKILL.
+    async fn stop(&self, child: &mut Child, stdin: &mut ChildStdin) -> Result<()> {
+        tracing::info!(
+            "Stopping {}, waiting for it to save its worlds...",
+            self.instance.name
+        );
+
+        if let Err(e) = send(stdin, "stop").await {
+            tracing::warn!("Failed to send stop to the server: {:#}", e);
+        } else if let Ok(status) = tokio::time::timeout(self.stop_timeout, child.wait()).await {
+            tracing::info!(
+                "{} saved its worlds and stopped ({})",
+                self.instance.name,
+                status?
+            );
+            return Ok(());
+        }
+
+        if let Some(pid) = child.id() {
+            tracing::warn!(
+                "{} did not stop in time, sending SIGTERM",
+                self.instance.name
+            );
+
+            // SAFETY: the child has not been reaped yet, so the pid still refers to it.
+            if unsafe { libc::kill(pid as libc::pid_t, libc::SIGTERM) } != 0 {
+                return Err(std::io::Error::last_os_error()).context("Failed to send SIGTERM");
+            }
+
+            if tokio::time::timeout(TERM_TIMEOUT, child.wait())
+                .await
+                .is_ok()
+            {
+                return Ok(());
+            }
+        }
+
+        tracing::warn!("{} ignored SIGTERM, killing it", self.instance.name);
+        child.kill().await.context("Failed to kill the server")
+    }
+}
+
+/// The command systemd runs, split on whitespace the way the unit's `bash -c` does.
+fn launch_command(server_dir: &Path) -> Result<Vec<String>> {
+    let config = ConfigGenerator::new(server_dir);
+    let java_executable = config
+        .read_feather_env("JAVA_EXECUTABLE")?
+        .context("feather.env has no JAVA_EXECUTABLE")?;
+
+    let mut command = vec![java_executable];
+    for key in ["JAVA_ARGS", "SERVER_ARGS"] {
+        if let Some(args) = config.read_feather_env(key)? {
+            command.extend(args.split_whitespace().map(str::to_string));
+        }
+    }
+    command.push("nogui".to_string());
+
+    Ok(command)
+}
+
+/// Next console line, pending forever once the console is closed.
+async fn next_line(console: &mut Option<Lines<BufReader<Stdin>>>) -> Option<String> {
+    match console {
+        Some(lines) => lines.next_line().await.ok().flatten(),
+        None => std::future::pending().await,
+    }
+}
+
+async fn send(stdin: &mut ChildStdin, line: &str) -> Result<()> {
+    stdin.write_all(format!("{line}\n").as_bytes()).await?;
+    stdin.flush().await?;
+    Ok(())
+}
@@ -0,0 +1,260 @@
+use anyhow::{Context, Result, anyhow};
+use rustc_hash::FxHashMap;
+use std::path::Path;
+
+const UNIT_DIR: &str = "/etc/systemd/system";
+const SERVER_TEMPLATE: &str = include_str!("templates/feather@.service");
+const PROXY_TEMPLATE: &str = include_str!("templates/feather-proxy@.service");
+/// File name of the server unit template, also looked up in the user's templates directory.
+const SERVER_TEMPLATE_NAME: &str = "feather@.service";
+/// Unit of the single server earlier versions installed.
+const LEGACY_UNIT_NAME: &str = "feather.service";
+
+/// Whether the host was booted with systemd, which containers usually are not.
+pub fn is_running() -> bool {
+    Path::new("/run/systemd/system").exists()
+}
+
+/// Name of the systemd unit running an instance.
+pub fn unit_name(instance: &str) -> String {
+    format!("feather@{instance}.service")
+}
+
+/// Renders the unit for an instance, preferring `feather@.service` in `templates_dir` over the
+/// built-in template.
+pub fn render_server_unit(
+    templates_dir: &Path,
+    instance: &str,
+    server_dir: &Path,
+    java_args: &[String],
+) -> Result<String> {
+    let override_path = templates_dir.join(SERVER_TEMPLATE_NAME);
+
+    let template = if override_path.exists() {
+        tracing::info!("Using systemd template {}", override_path.display());
+
+        std::fs::read_to_string(&override_path)
+            .with_context(|| format!("Failed to read {}", override_path.display()))?
+    } else {
+        SERVER_TEMPLATE.to_string()
+    };
+
+    Ok(template
+        .replace("{{INSTANCE}}", instance)
+        .replace("{{SERVER_DIR}}", &server_dir.display().to_string())
+        .replace("{{MEMORY_MAX}}", &memory_max(java_args)))
+}
+
+/// Name of the systemd unit running a proxy, one per proxy kind.
+pub fn proxy_unit_name(kind: &str) -> String {
+    format!("feather-proxy@{kind}.service")
+}
+
+/// Renders the unit for a proxy, hardened the same way as the server units.
+pub fn render_proxy_unit(kind: &str, proxy_dir: &Path, java_args: &[String]) -> String {
+    PROXY_TEMPLATE
+        .replace("{{PROXY}}", kind)
+        .replace("{{PROXY_DIR}}", &proxy_dir.display().to_string())
+        .replace("{{MEMORY_MAX}}", &memory_max(java_args))
+}
+
+/// `MemoryMax=` for a JVM started with `java_args`, unlimited without `-Xmx`.
+fn memory_max(java_args: &[String]) -> String {
+    max_heap(java_args)
+        // Metaspace, thread stacks and direct buffers live outside the heap.
+        .map(|heap| (heap + heap / 4).max(heap + 512 * 1024 * 1024).to_string())
+        .unwrap_or_else(|| "infinity".to_string())
+}
+
+/// Writes a unit to the systemd unit directory, reloading systemd only when it changed.
+pub fn install_unit(unit_name: &str, content: &str) -> Result<()> {
+    let service_path = Path::new(UNIT_DIR).join(unit_name);
+
+    if std::fs::read_to_string(&service_path).is_ok_and(|existing| existing == content) {
+        tracing::debug!("{} is up to date", service_path.display());
+        return Ok(());
+    }
+
+    std::fs::write(&service_path, content).with_context(|| {
+        format!(
+            "Failed to write systemd service file: {}",
+            service_path.display()
+        )
+    })?;
+
+    daemon_reload()
+}
+
+/// Disables and removes the single `feather.service` earlier versions installed, which runs
+/// whatever is left in the shared servers directory and would otherwise start at boot next to
+/// the instance units.
+pub fn remove_legacy_unit() -> Result<()> {
+    let service_path = Path::new(UNIT_DIR).join(LEGACY_UNIT_NAME);
+
+    if !service_path.exists() {
+        return Ok(());
+    }
+
+    tracing::info!("Disabling the legacy {}...", LEGACY_UNIT_NAME);
+
+    systemctl("disable", LEGACY_UNIT_NAME)?;
+    std::fs::remove_file(&service_path)
+        .with_context(|| format!("Failed to remove {}", service_path.display()))?;
+
+    daemon_reload()?;
+
+    tracing::warn!(
+        "Removed the legacy {}, stop a server it still runs with `systemctl stop {}`",
+        LEGACY_UNIT_NAME,
+        LEGACY_UNIT_NAME
+    );
+    Ok(())
+}
+
+fn daemon_reload() -> Result<()> {
+    let status = std::process::Command::new("sudo")
+        .args(["systemctl", "daemon-reload"])
+        .status()
+        .context("Failed to reload systemd daemon")?;
+
+    if !status.success() {
+        return Err(anyhow!("Failed to reload systemd daemon"));
+    }
+
+    Ok(())
+}
+
+/// Runs `systemctl <action> <unit>`, e.g. to start or enable an instance.
+pub fn systemctl(action: &str, unit_name: &str) -> Result<()> {
+    let status = std::process::Command::new("sudo")
+        .args(["systemctl", action, unit_name])
+        .status()
+        .with_context(|| format!("Failed to run systemctl {action} {unit_name}"))?;
+
+    if !status.success() {
+        return Err(anyhow!("systemctl {} {} failed", action, unit_name));
+    }
+
+    Ok(())
+}
+
+/// Reads unit properties such as `ActiveState` or `MainPID`.
+pub fn show(unit_name: &str, properties: &[&str]) -> Result<FxHashMap<String, String>> {
+    let output = std::process::Command::new("systemctl")
+        .args(["show", unit_name, "--property", &properties.join(",")])
+        .output()
+        .with_context(|| format!("Failed to run systemctl show {unit_name}"))?;
+
+    if !output.status.success() {
+        return Err(anyhow!("systemctl show {} failed", unit_name));
+    }
+
+    Ok(String::from_utf8_lossy(&output.stdout)
+        .lines()
+        .filter_map(|line| line.split_once('='))
+        .map(|(key, value)| (key.to_string(), value.to_string()))
+        .collect())
+}
+
+/// PID of the running server, `None` when the unit is not running.
+pub fn main_pid(unit_name: &str) -> Result<Option<u32>> {
+    Ok(show(unit_name, &["MainPID"])?
+        .get("MainPID")
+        .and_then(|pid| pid.parse::<u32>().ok())
+        .filter(|pid| *pid != 0))
+}
+
+/// The last `lines` lines the unit logged.
+pub fn journal(unit_name: &str, lines: usize) -> Result<String> {
+    let output = std::process::Command::new("sudo")
+        .args([
+            "journalctl",
+            "--unit",
+            unit_name,
+            "--no-pager",
+            "--output",
+            "cat",
+        ])
+        .arg(format!("--lines={lines}"))
+        .output()
+        .with_context(|| format!("Failed to read the journal of {unit_name}"))?;
+
+    Ok(String::from_utf8_lossy(&output.stdout).into_owned())
+}
+
+/// Maximum heap in bytes from the last `-Xmx` argument, which is the one the JVM honours.
+fn max_heap(java_args: &[String]) -> Option<u64> {
+    let value = java_args
+        .iter()
+        .rev()
+        .find_map(|arg| arg.strip_prefix("-Xmx"))?;
+
+    let (digits, multiplier) = match value.chars().last()? {
+        'k' | 'K' => (&value[..value.len() - 1], 1 << 10),
+        'm' | 'M' => (&value[..value.len() - 1], 1 << 20),
+        'g' | 'G' => (&value[..value.len() - 1], 1 << 30),
+        't' | 'T' => (&value[..value.len() - 1], 1 << 40),
+        _ => (value, 1),
+    };
+
+    digits.parse::<u64>().ok()?.checked_mul(multiplier)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn args(args: &[&str]) -> Vec<String> {
+        args.iter().map(|arg| arg.to_string()).collect()
+    }
+
+    #[test]
+    fn proxy_units_are_per_kind_and_hardened() {
+        assert_eq!(
+            proxy_unit_name("velocity"),
+            "feather-proxy@velocity.service"
+        );
+
+        let unit = render_proxy_unit(
+            "waterfall",
+            Path::new("/opt/feather/proxies/waterfall"),
+            &args(&["-Xmx1G"]),
+        );
+        assert!(!unit.contains("{{"));
+        assert!(unit.contains("WorkingDirectory=/opt/feather/proxies/waterfall\n"));
+        assert!(unit.contains("ReadWritePaths=/opt/feather/proxies/waterfall\n"));
+        assert!(unit.contains("NoNewPrivileges=true\n"));
+        assert!(unit.contains(&format!("MemoryMax={}\n", (1u64 << 30) + 512 * 1024 * 1024)));
+    }
+
+    #[test]
+    fn max_heap_reads_units() {
+        assert_eq!(max_heap(&args(&["-Xmx4G"])), Some(4 << 30));
+        assert_eq!(max_heap(&args(&["-Xmx4096m"])), Some(4 << 30));
+        assert_eq!(max_heap(&args(&["-Xmx512k"])), Some(512 << 10));
+        assert_eq!(max_heap(&args(&["-Xmx1t"])), Some(1 << 40));
+        assert_eq!(max_heap(&args(&["-Xmx1073741824"])), Some(1 << 30));
+    }
+
+    #[test]
+    fn max_heap_uses_last_flag() {
+        assert_eq!(
+            max_heap(&args(&["-Xmx2G", "-Xms1G", "-Xmx6G"])),
+            Some(6 << 30)
+        );
+    }
+
+    #[test]
+    fn max_heap_ignores_other_flags() {
+        assert_eq!(max_heap(&args(&[])), None);
+        assert_eq!(max_heap(&args(&["-Xms4G", "-XX:+UseG1GC"])), None);
+    }
+
+    #[test]
+    fn max_heap_rejects_invalid_values() {
+        assert_eq!(max_heap(&args(&["-Xmx"])), None);
+        assert_eq!(max_heap(&args(&["-Xmx4X"])), None);
+        assert_eq!(max_heap(&args(&["-Xmx-1G"])), None);
+        assert_eq!(max_heap(&args(&["-Xmx99999999999T"])), None);
+    }
+}